@@ -118,11 +118,31 @@ function createFileBox(file, xhr) {
         document.body.removeChild(fakeInput);
     });
 
+    // only shown once the server hands us a deletion token.
+    const deleteButton = document.createElement('button');
+    deleteButton.classList.add('button');
+    deleteButton.classList.add('is-danger');
+    deleteButton.classList.add('is-outlined');
+    deleteButton.textContent = 'Delete';
+    deleteButton.style.display = 'none';
+    deleteButton.addEventListener('click', function(ev) {
+        ev.preventDefault();
+        if (!confirm(`Delete ${url.textContent}?`)) return;
+        deleteUpload(box, url, deleteButton.dataset.token);
+    });
+
+    const token = document.createElement('small');
+    token.style.display = 'none';
+
     const contentBox = document.createElement('p');
     contentBox.classList.add('has-text-centered');
     contentBox.appendChild(url);
     contentBox.appendChild(document.createElement('br'));
     contentBox.appendChild(copyUrlButton);
+    contentBox.appendChild(document.createTextNode(' '));
+    contentBox.appendChild(deleteButton);
+    contentBox.appendChild(document.createElement('br'));
+    contentBox.appendChild(token);
 
     const contentContentBox = document.createElement('div');
     contentContentBox.classList.add('content');
@@ -144,14 +164,36 @@ function createFileBox(file, xhr) {
     box.style.padding='5px';
     box.appendChild(mediaContainer);
 
-    return { box, url };
+    return { box, url, deleteButton, token };
+}
+
+function deleteUpload(box, url, token) {
+    const xhr = new XMLHttpRequest();
+    // the link may point at a different host, but the path is the same here.
+    xhr.open('DELETE', new URL(url.href).pathname);
+    xhr.setRequestHeader('X-Delete-Token', token);
+    xhr.addEventListener('loadend', function() {
+        let res;
+        try {
+            res = JSON.parse(xhr.responseText);
+        }
+        catch (e) {
+            res = { status: 'error', msg: `Could not delete. HTTP Code: ${xhr.status}` };
+        }
+        if (xhr.status != 200 || res.status == 'error') {
+            return setFailBanner(res.msg || 'unknown error');
+        }
+        if (box.parentNode) box.parentNode.removeChild(box);
+        setSuccessBanner(`Deleted ${url.textContent}`);
+    });
+    xhr.send();
 }
 
 function finishedUpload(ev) {
     dropzone.textContent = 'Select or Drop Files';
 
     const xhr = ev.target;
-    const { box, url }  = xhr[xhrExt];
+    const { box, url, deleteButton, token }  = xhr[xhrExt];
 
     let res;
 
@@ -178,6 +220,13 @@ function finishedUpload(ev) {
 
     url.href = res.msg;
     url.textContent = res.msg;
+    if (res.delete_token) {
+        deleteButton.dataset.token = res.delete_token;
+        deleteButton.style.display = '';
+        // keep it to delete the upload later, with an X-Delete-Token header.
+        token.textContent = `Deletion token: ${res.delete_token}`;
+        token.style.display = '';
+    }
     setSuccessBanner('Successfully Uploaded');
     document.getElementById('uploads').appendChild(box);
 }
@@ -0,0 +1,717 @@
+// Copyright (c) 2026, Anthony DeDominic <adedomin@gmail.com>
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    net::ToSocketAddrs,
+    path::{Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config::{ConfigError, Kind, StorageState, Upload, config_path, load_config},
+    models::{
+        dropfs::DropFsGuard,
+        meta::{UploadMeta, unix_now},
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum AdminErr {
+    #[error("{0}")]
+    Usage(String),
+    #[error("No store named \"{0}\".")]
+    NoStore(String),
+    #[error("I/O Error: {0}")]
+    Io(#[from] io::Error),
+    #[error("{0}")]
+    Config(#[from] ConfigError),
+    #[error("{0} problem(s) found.")]
+    Problems(usize),
+    #[error("Invalid archive: {0}")]
+    Archive(String),
+}
+
+/// What to do instead of serving.
+pub enum Command {
+    List(Option<String>),
+    Rm(String, Vec<String>),
+    Purge(u64, Option<String>),
+    Stats(Option<String>),
+    Verify(Option<String>),
+    CheckConfig,
+    Export(String, PathBuf),
+    Import(String, PathBuf),
+}
+
+const COMMANDS: [&str; 8] = [
+    "list",
+    "rm",
+    "purge",
+    "stats",
+    "verify",
+    "check-config",
+    "export",
+    "import",
+];
+
+/// e.g. 3600, 90s, 45m, 12h or 30d, in seconds.
+fn parse_duration(dur: &str) -> Option<u64> {
+    let (num, unit) = match dur.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => dur.split_at(i),
+        None => (dur, "s"),
+    };
+    let mul = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return None,
+    };
+    num.parse::<u64>().ok()?.checked_mul(mul)
+}
+
+/// Takes the arguments after the program name: [ config.json ] [ command [ args... ] ]
+pub fn parse_args(
+    args: impl Iterator<Item = OsString>,
+) -> Result<(Option<PathBuf>, Option<Command>), AdminErr> {
+    let mut args = args.peekable();
+    // a config path may come first, as it always has.
+    let config = match args.peek().and_then(|a| a.to_str()) {
+        Some(cmd) if COMMANDS.contains(&cmd) => None,
+        _ => args.next().map(PathBuf::from),
+    };
+    let mut args = args.map(|a| {
+        a.into_string()
+            .map_err(|a| AdminErr::Usage(format!("Invalid argument: {a:?}")))
+    });
+    let Some(cmd) = args.next().transpose()? else {
+        return Ok((config, None));
+    };
+    let mut rest = args.collect::<Result<Vec<_>, _>>()?.into_iter();
+    let cmd = match cmd.as_str() {
+        "list" => Command::List(rest.next()),
+        "stats" => Command::Stats(rest.next()),
+        "verify" => Command::Verify(rest.next()),
+        "check-config" => Command::CheckConfig,
+        "rm" => {
+            let store = rest
+                .next()
+                .ok_or_else(|| AdminErr::Usage("rm needs a store.".to_owned()))?;
+            let names: Vec<_> = rest.by_ref().collect();
+            if names.is_empty() {
+                return Err(AdminErr::Usage("rm needs files to remove.".to_owned()));
+            }
+            Command::Rm(store, names)
+        }
+        "export" | "import" => {
+            let (Some(store), Some(archive)) = (rest.next(), rest.next()) else {
+                return Err(AdminErr::Usage(format!(
+                    "{cmd} needs a store and an archive."
+                )));
+            };
+            match cmd.as_str() {
+                "export" => Command::Export(store, archive.into()),
+                _ => Command::Import(store, archive.into()),
+            }
+        }
+        "purge" => {
+            if rest.next().as_deref() != Some("--older-than") {
+                return Err(AdminErr::Usage("purge needs --older-than.".to_owned()));
+            }
+            let older_than = rest
+                .next()
+                .as_deref()
+                .and_then(parse_duration)
+                .ok_or_else(|| AdminErr::Usage("Invalid --older-than duration.".to_owned()))?;
+            Command::Purge(older_than, rest.next())
+        }
+        other => return Err(AdminErr::Usage(format!("Unknown command: {other}"))),
+    };
+    if let Some(extra) = rest.next() {
+        return Err(AdminErr::Usage(format!("Unexpected argument: {extra}")));
+    }
+    Ok((config, Some(cmd)))
+}
+
+/// Seconds since the UNIX epoch as e.g. 2026-01-02T03:04:05Z
+fn fmt_time(secs: u64) -> String {
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let (days, rem) = ((secs / 86400) as i64, secs % 86400);
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    format!(
+        "{y:04}-{m:02}-{d:02}T{:02}:{:02}:{:02}Z",
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60
+    )
+}
+
+fn select<'a>(
+    stores: &'a [StorageState],
+    name: Option<&str>,
+) -> Result<Vec<&'a StorageState>, AdminErr> {
+    match name {
+        None => Ok(stores.iter().collect()),
+        Some(name) => stores
+            .iter()
+            .find(|s| s.name() == name)
+            .map(|s| vec![s])
+            .ok_or_else(|| AdminErr::NoStore(name.to_owned())),
+    }
+}
+
+/// Oldest first.
+async fn sorted_uploads(store: &StorageState) -> io::Result<Vec<Upload>> {
+    let mut uploads = store.uploads().await?;
+    uploads.sort_unstable_by_key(Upload::created);
+    Ok(uploads)
+}
+
+async fn list(stores: Vec<&StorageState>) -> Result<(), AdminErr> {
+    for store in stores {
+        for upload in sorted_uploads(store).await? {
+            println!(
+                "{}\t{}\t{}\t{}",
+                store.name(),
+                upload.entry.name,
+                upload.entry.size,
+                fmt_time(upload.created())
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn rm(store: &StorageState, names: Vec<String>) -> Result<(), AdminErr> {
+    let mut problems = 0;
+    for name in names {
+        match store.delete(&name).await {
+            Ok(()) => println!("removed {name}"),
+            Err(e) => {
+                eprintln!("{name}: {e}");
+                problems += 1;
+            }
+        }
+    }
+    match problems {
+        0 => Ok(()),
+        n => Err(AdminErr::Problems(n)),
+    }
+}
+
+async fn purge(stores: Vec<&StorageState>, older_than: u64) -> Result<(), AdminErr> {
+    let cutoff = unix_now().saturating_sub(older_than);
+    for store in stores {
+        let mut purged = 0;
+        for upload in sorted_uploads(store).await? {
+            if upload.created() >= cutoff {
+                break;
+            }
+            match store.delete(&upload.entry.name).await {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => purged += 1,
+            }
+        }
+        println!("{}: purged {purged} files", store.name());
+    }
+    Ok(())
+}
+
+async fn stats(stores: Vec<&StorageState>) -> Result<(), AdminErr> {
+    println!("store\tfiles\tbytes\toldest\tnewest");
+    for store in stores {
+        let uploads = sorted_uploads(store).await?;
+        let bytes: u64 = uploads.iter().map(|u| u.entry.size).sum();
+        let when = |u: Option<&Upload>| u.map(|u| fmt_time(u.created())).unwrap_or("-".into());
+        println!(
+            "{}\t{}\t{bytes}\t{}\t{}",
+            store.name(),
+            uploads.len(),
+            when(uploads.first()),
+            when(uploads.last()),
+        );
+    }
+    Ok(())
+}
+
+/// SHA-256 of an upload as it was sent, so compressed pastes are hashed decompressed.
+async fn hash_upload(store: &StorageState, name: &str) -> io::Result<String> {
+    let mut obj = store.backend().get(name).await?;
+    let mut hasher = Sha256::new();
+    if store.kind() == Kind::Text {
+        let mut buf = vec![];
+        while let Some(chunk) = obj.body.next().await {
+            buf.extend_from_slice(&chunk?);
+        }
+        if buf.starts_with(&[0x1f, 0x8b]) {
+            io::copy(&mut GzDecoder::new(buf.as_slice()), &mut hasher)?;
+        } else {
+            hasher.update(&buf);
+        }
+    } else {
+        while let Some(chunk) = obj.body.next().await {
+            hasher.update(chunk?);
+        }
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+async fn verify(stores: Vec<&StorageState>) -> Result<(), AdminErr> {
+    let mut problems = 0;
+    for store in stores {
+        let uploads = store.uploads().await?;
+        let mut report = |name: &str, what: &str| {
+            println!("{}\t{name}\t{what}", store.name());
+            problems += 1;
+        };
+        for upload in &uploads {
+            let name = &upload.entry.name;
+            let Some(meta) = &upload.meta else {
+                report(name, "no metadata");
+                continue;
+            };
+            if meta.size != upload.entry.size {
+                report(name, "size differs from metadata");
+            }
+            match hash_upload(store, name).await {
+                Ok(sha256) if sha256 != meta.sha256 => report(name, "sha256 differs from metadata"),
+                Ok(_) => (),
+                Err(e) => report(name, &format!("unreadable: {e}")),
+            }
+        }
+        for name in store.meta_names()? {
+            if !uploads.iter().any(|u| u.entry.name == name) {
+                report(&name, "metadata without a file");
+            }
+        }
+    }
+    match problems {
+        0 => Ok(()),
+        n => Err(AdminErr::Problems(n)),
+    }
+}
+
+/// The first entry of an export.
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    store: String,
+    kind: Kind,
+    /// Oldest first, the order they are evicted in.
+    uploads: Vec<ManifestUpload>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ManifestUpload {
+    name: String,
+    created: u64,
+}
+
+const MANIFEST: &str = "manifest.json";
+
+fn append<W: Write>(
+    tar: &mut tar::Builder<W>,
+    path: &str,
+    mtime: u64,
+    data: &[u8],
+) -> io::Result<()> {
+    append_from(tar, path, mtime, data.len() as u64, data)
+}
+
+/// Like [`append`], for size bytes read from data.
+fn append_from<W: Write, R: Read>(
+    tar: &mut tar::Builder<W>,
+    path: &str,
+    mtime: u64,
+    size: u64,
+    data: R,
+) -> io::Result<()> {
+    let mut header = tar::Header::new_ustar();
+    header.set_size(size);
+    // state/secret signs deletion tokens, so only its owner may read it once extracted.
+    header.set_mode(if path.starts_with("state/") {
+        0o600
+    } else {
+        0o644
+    });
+    header.set_mtime(mtime);
+    tar.append_data(&mut header, path, data)
+}
+
+/// Create or truncate a file only its owner can read.
+fn create_private(path: &Path) -> io::Result<File> {
+    let mut opts = std::fs::OpenOptions::new();
+    opts.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut opts, 0o600);
+    let file = opts.open(path)?;
+    // the mode only applies to new files.
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    Ok(file)
+}
+
+/// Write a store to a tar archive: manifest.json, then state/, meta/ and files/ entries.
+/// The archive holds the store's secret, so it is created readable only by its owner.
+async fn export(store: &StorageState, archive: &Path) -> Result<(), AdminErr> {
+    let uploads = sorted_uploads(store).await?;
+    let mut tar = tar::Builder::new(BufWriter::new(create_private(archive)?));
+    let now = unix_now();
+    let manifest = Manifest {
+        store: store.name().to_owned(),
+        kind: store.kind(),
+        uploads: uploads
+            .iter()
+            .map(|u| ManifestUpload {
+                name: u.entry.name.clone(),
+                created: u.created(),
+            })
+            .collect(),
+    };
+    append(
+        &mut tar,
+        MANIFEST,
+        now,
+        &serde_json::to_vec_pretty(&manifest).map_err(io::Error::from)?,
+    )?;
+    for (name, contents) in store.export_state()? {
+        append(&mut tar, &format!("state/{name}"), now, &contents)?;
+    }
+    for upload in &uploads {
+        let name = &upload.entry.name;
+        if let Some(meta) = &upload.meta {
+            let meta = serde_json::to_vec(meta).map_err(io::Error::from)?;
+            append(
+                &mut tar,
+                &format!("meta/{name}.json"),
+                upload.created(),
+                &meta,
+            )?;
+        }
+        let path = format!("files/{name}");
+        if let Some(dir) = store.backend().local_dir() {
+            let file = File::open(dir.file(name))?;
+            let size = file.metadata()?.len();
+            append_from(&mut tar, &path, upload.created(), size, file)?;
+            continue;
+        }
+        // tar needs the size up front, and a remote backend may not know it until the end.
+        let mut obj = store.backend().get(name).await?;
+        let mut contents = vec![];
+        while let Some(chunk) = obj.body.next().await {
+            contents.extend_from_slice(&chunk?);
+        }
+        append(&mut tar, &path, upload.created(), &contents)?;
+    }
+    tar.into_inner()?.flush()?;
+    println!(
+        "{}: exported {} files to {}",
+        store.name(),
+        uploads.len(),
+        archive.display()
+    );
+    Ok(())
+}
+
+/// Names from an archive end up in paths, so only take what we could have made.
+fn plain_name(name: &str) -> bool {
+    !name.starts_with('.')
+        && name
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, b'-' | b'_' | b'.'))
+}
+
+/// Restore an export into a store, keeping every name.
+/// The exported store's secret and ID generator come along only if this store is empty,
+/// otherwise the deletion tokens of the uploads already here would stop working.
+/// A running server only counts imported files against its limits after a restart.
+async fn import(store: &StorageState, archive: &Path) -> Result<(), AdminErr> {
+    let mut tar = tar::Archive::new(BufReader::new(File::open(archive)?));
+    let mut entries = tar.entries()?;
+    let bad = |why: &str| AdminErr::Archive(why.to_owned());
+    let manifest: Manifest = match entries.next().transpose()? {
+        Some(entry) if entry.path()?.as_os_str() == MANIFEST => {
+            serde_json::from_reader(entry).map_err(|e| bad(&e.to_string()))?
+        }
+        _ => return Err(bad("it doesn't start with manifest.json.")),
+    };
+    if manifest.kind != store.kind() {
+        return Err(bad(&format!(
+            "it holds a {:?} store, {} is {:?}.",
+            manifest.kind,
+            store.name(),
+            store.kind()
+        )));
+    }
+    let created: HashMap<_, _> = manifest
+        .uploads
+        .into_iter()
+        .map(|u| (u.name, u.created))
+        .collect();
+    let take_state = store.uploads().await?.is_empty();
+    if !take_state {
+        println!(
+            "{} has uploads, keeping its secret: deletion tokens of imported files won't work.",
+            store.name()
+        );
+    }
+    store.create()?;
+
+    let mut metas = HashMap::new();
+    let (mut imported, mut problems) = (0, 0);
+    for entry in entries {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        let (Some(dir), Some(name)) = (
+            path.parent().and_then(|p| p.to_str()),
+            path.file_name().and_then(|n| n.to_str()),
+        ) else {
+            return Err(bad(&format!("unexpected entry {}", path.display())));
+        };
+        if !plain_name(name) {
+            return Err(bad(&format!("unexpected name {name}")));
+        }
+        match dir {
+            "state" if take_state => {
+                let mut contents = vec![];
+                entry.read_to_end(&mut contents)?;
+                store.import_state(name, &contents)?;
+            }
+            "state" => (),
+            "meta" => {
+                let meta: UploadMeta =
+                    serde_json::from_reader(&mut entry).map_err(|e| bad(&e.to_string()))?;
+                let name = name.strip_suffix(".json").unwrap_or(name);
+                metas.insert(name.to_owned(), meta);
+            }
+            "files" => {
+                if store.backend().stat(name).await?.is_some() {
+                    println!("{}\t{name}\talready exists, skipped", store.name());
+                    problems += 1;
+                    continue;
+                }
+                let (staged, file) = store.create_staging().await?;
+                let fguard = DropFsGuard::new(&staged);
+                let mut file = BufWriter::new(file.into_std().await);
+                io::copy(&mut entry, &mut file)?;
+                file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+                store.publish_as(&staged, name).await?;
+                fguard.defuse();
+                // uploads from before metadata was kept still need a place in the FIFO.
+                let meta = match metas.remove(name) {
+                    Some(meta) => meta,
+                    None => UploadMeta {
+                        created: created.get(name).copied().unwrap_or_else(unix_now),
+                        size: entry.size(),
+                        ext: name
+                            .rsplit_once('.')
+                            .map(|(_, ext)| ext)
+                            .unwrap_or_default()
+                            .to_owned(),
+                        sha256: hash_upload(store, name).await?,
+                        ip_hash: None,
+                    },
+                };
+                store.write_meta(name, &meta).await?;
+                imported += 1;
+            }
+            _ => return Err(bad(&format!("unexpected entry {}", path.display()))),
+        }
+    }
+    println!("{}: imported {imported} files", store.name());
+    match problems {
+        0 => Ok(()),
+        n => Err(AdminErr::Problems(n)),
+    }
+}
+
+/// Print the effective config, and fail if anything in it looks wrong.
+fn check_config(path: Option<PathBuf>) -> Result<(), AdminErr> {
+    let path = config_path(path);
+    println!("config: {}", path.display());
+    let config = load_config(Some(path))?;
+    let mut problems = 0;
+    for key in config.unknown_keys() {
+        println!("PROBLEM: unknown key {key}");
+        problems += 1;
+    }
+
+    let bind = config.get_bind_addr();
+    println!("bind: {bind}");
+    if !config.is_unix_listener()
+        && let Err(e) = bind.to_socket_addrs()
+    {
+        println!("PROBLEM: can't resolve bind address: {e}");
+        problems += 1;
+    }
+    match config.link_prefix.as_str() {
+        "" => println!("link_prefix: none, links are relative"),
+        prefix => println!("link_prefix: {prefix}"),
+    }
+    println!("auth_tokens: {}", config.auth_token_count());
+    match &config.ratelim {
+        Some(r) => println!(
+            "ratelim: {} per {}s, trust_headers: {}, bucket_size: {}",
+            r.burst(),
+            r.secs().as_secs(),
+            r.trust_headers(),
+            r.bucket_size()
+        ),
+        None => println!("ratelim: off"),
+    }
+
+    // only resolves and checks the stores, nothing is created or migrated.
+    match config.get_stores() {
+        Ok(stores) => {
+            for store in stores {
+                println!("\nstore {}", store.name());
+                for line in store.describe().lines() {
+                    println!("  {line}");
+                }
+            }
+        }
+        Err(e) => {
+            println!("PROBLEM: {e}");
+            problems += 1;
+        }
+    }
+
+    match problems {
+        0 => Ok(()),
+        n => Err(AdminErr::Problems(n)),
+    }
+}
+
+pub async fn run(cmd: Command, config_path: Option<PathBuf>) -> Result<(), AdminErr> {
+    if let Command::CheckConfig = cmd {
+        return check_config(config_path);
+    }
+    // these may run next to a live server, so leave the stores as they are.
+    let stores = load_config(config_path)?.get_stores()?;
+    match cmd {
+        Command::CheckConfig => Ok(()),
+        Command::List(store) => list(select(&stores, store.as_deref())?).await,
+        Command::Rm(store, names) => rm(select(&stores, Some(&store))?[0], names).await,
+        Command::Purge(older_than, store) => {
+            purge(select(&stores, store.as_deref())?, older_than).await
+        }
+        Command::Stats(store) => stats(select(&stores, store.as_deref())?).await,
+        Command::Verify(store) => verify(select(&stores, store.as_deref())?).await,
+        Command::Export(store, archive) => {
+            export(select(&stores, Some(&store))?[0], &archive).await
+        }
+        Command::Import(store, archive) => {
+            import(select(&stores, Some(&store))?[0], &archive).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<(Option<PathBuf>, Option<Command>), AdminErr> {
+        parse_args(args.iter().map(OsString::from))
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("3600"), Some(3600));
+        assert_eq!(parse_duration("90s"), Some(90));
+        assert_eq!(parse_duration("45m"), Some(45 * 60));
+        assert_eq!(parse_duration("12h"), Some(12 * 60 * 60));
+        assert_eq!(parse_duration("7d"), Some(7 * 24 * 60 * 60));
+        assert_eq!(parse_duration("99999999999d"), Some(99999999999 * 86400));
+        assert_eq!(parse_duration("999999999999999d"), None);
+        assert_eq!(parse_duration("99999999999999999999"), None);
+        assert_eq!(parse_duration("3w"), None);
+        assert_eq!(parse_duration("5ms"), None);
+        assert_eq!(parse_duration("-5m"), None);
+        assert_eq!(parse_duration("m"), None);
+        assert_eq!(parse_duration(""), None);
+    }
+
+    #[test]
+    fn times() {
+        assert_eq!(fmt_time(0), "1970-01-01T00:00:00Z");
+        assert_eq!(fmt_time(1709251199), "2024-02-29T23:59:59Z");
+        assert_eq!(fmt_time(1709251200), "2024-03-01T00:00:00Z");
+        assert_eq!(fmt_time(951782400), "2000-02-29T00:00:00Z");
+        // not a leap year.
+        assert_eq!(fmt_time(4107542400 - 1), "2100-02-28T23:59:59Z");
+    }
+
+    #[test]
+    fn config_and_command() {
+        assert!(matches!(args(&[]), Ok((None, None))));
+        assert!(matches!(
+            args(&["c.json"]),
+            Ok((Some(path), None)) if path == Path::new("c.json")
+        ));
+        assert!(matches!(
+            args(&["c.json", "purge", "--older-than", "7d", "image"]),
+            Ok((Some(_), Some(Command::Purge(604800, Some(store))))) if store == "image"
+        ));
+        assert!(matches!(
+            args(&["rm", "image", "a.png", "b.png"]),
+            Ok((None, Some(Command::Rm(_, names)))) if names == ["a.png", "b.png"]
+        ));
+        assert!(matches!(args(&["rm", "image"]), Err(AdminErr::Usage(_))));
+        assert!(matches!(args(&["purge", "7d"]), Err(AdminErr::Usage(_))));
+        assert!(matches!(
+            args(&["purge", "--older-than", "7w"]),
+            Err(AdminErr::Usage(_))
+        ));
+        assert!(matches!(
+            args(&["list", "image", "paste"]),
+            Err(AdminErr::Usage(_))
+        ));
+        assert!(matches!(
+            args(&["c.json", "serve"]),
+            Err(AdminErr::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn config_named_like_a_command() {
+        // a command comes first, so the config needs a path that doesn't look like one.
+        assert!(matches!(
+            args(&["list"]),
+            Ok((None, Some(Command::List(None))))
+        ));
+        assert!(matches!(
+            args(&["list", "list"]),
+            Ok((None, Some(Command::List(Some(store))))) if store == "list"
+        ));
+        assert!(matches!(
+            args(&["./list", "list"]),
+            Ok((Some(path), Some(Command::List(None)))) if path == Path::new("./list")
+        ));
+        assert!(matches!(args(&["./purge"]), Ok((Some(_), None))));
+    }
+}
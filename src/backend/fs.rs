@@ -0,0 +1,211 @@
+// Copyright (c) 2026, Anthony DeDominic <adedomin@gmail.com>
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+use std::{
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use futures_util::{FutureExt, StreamExt, future::BoxFuture};
+use serde::Deserialize;
+use tokio_util::io::ReaderStream;
+
+use crate::backend::{Backend, Entry, LocalDir, Object};
+
+/// Unknown keys are errors, see [`super::BackendSettings`].
+#[derive(Deserialize, Default, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FsSettings {
+    /// Keep files in two levels of prefix directories, e.g. abc123.png -> a/b/abc123.png
+    #[serde(default)]
+    shard: bool,
+}
+
+/// The two prefix directories a name lives under in a sharded store.
+/// Short names are padded with '_' so every file sits at the same depth.
+fn shard_dirs(name: &str) -> [String; 2] {
+    let mut stem = name.split('.').next().unwrap_or_default().chars();
+    [(); 2].map(|_| stem.next().unwrap_or('_').to_string())
+}
+
+/// Path of a name relative to the base of a sharded store, using '/' as the separator.
+pub fn shard_path(name: &str) -> String {
+    let [a, b] = shard_dirs(name);
+    format!("{a}/{b}/{name}")
+}
+
+pub struct FsBackend {
+    base: PathBuf,
+    shard: bool,
+}
+
+impl FsBackend {
+    pub fn new(base: PathBuf, settings: FsSettings) -> Self {
+        Self {
+            base,
+            shard: settings.shard,
+        }
+    }
+
+    /// Names come from URLs, so never let one escape the base directory.
+    fn path(&self, name: &str) -> io::Result<PathBuf> {
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+            return Err(io::Error::new(ErrorKind::NotFound, "Invalid file name."));
+        }
+        if self.shard {
+            Ok(self.base.join(shard_path(name)))
+        } else {
+            Ok(self.base.join(name))
+        }
+    }
+}
+
+/// Move the files of a directory that used to be flat into their shards,
+/// e.g. base/abc123.png -> base/a/b/abc123.png. Afterwards there are no files left at the top level.
+pub fn migrate_flat(base: &Path) -> io::Result<()> {
+    let mut moved = 0usize;
+    for file in std::fs::read_dir(base)? {
+        let file = file?;
+        if !file.file_type()?.is_file() {
+            continue;
+        }
+        let name = file.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+        let dest = base.join(shard_path(&name));
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Link then unlink, like uploads, so a file already in a shard is never replaced.
+        match std::fs::hard_link(file.path(), &dest) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                eprintln!("WARN: not migrating {name}: it already exists in its shard.");
+                continue;
+            }
+            Err(e) => return Err(e),
+        }
+        std::fs::remove_file(file.path())?;
+        moved += 1;
+    }
+    if moved != 0 {
+        eprintln!(
+            "WARN: migrated {moved} files in {} to the sharded layout.",
+            base.display()
+        );
+    }
+    Ok(())
+}
+
+async fn read_files(dir: &Path, entries: &mut Vec<Entry>) -> io::Result<()> {
+    let mut read_dir = tokio::fs::read_dir(dir).await?;
+    while let Some(file) = read_dir.next_entry().await? {
+        let meta = file.metadata().await?;
+        if meta.is_file() {
+            let name = file.file_name().to_string_lossy().into_owned();
+            entries.push(to_entry(name, meta)?);
+        }
+    }
+    Ok(())
+}
+
+async fn read_subdirs(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut dirs = vec![];
+    let mut read_dir = tokio::fs::read_dir(dir).await?;
+    while let Some(file) = read_dir.next_entry().await? {
+        if file.file_type().await?.is_dir() {
+            dirs.push(file.path());
+        }
+    }
+    Ok(dirs)
+}
+
+fn to_entry(name: String, meta: std::fs::Metadata) -> io::Result<Entry> {
+    Ok(Entry {
+        name,
+        size: meta.len(),
+        modified: meta.modified()?,
+    })
+}
+
+impl Backend for FsBackend {
+    fn put<'a>(&'a self, name: &'a str, staged: &'a Path) -> BoxFuture<'a, io::Result<()>> {
+        // Hard links never replace an existing file, so older uploads can't be clobbered.
+        async move {
+            let path = self.path(name)?;
+            if self.shard
+                && let Some(parent) = path.parent()
+            {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::hard_link(staged, path).await
+        }
+        .boxed()
+    }
+
+    fn get<'a>(&'a self, name: &'a str) -> BoxFuture<'a, io::Result<Object>> {
+        async move {
+            let file = tokio::fs::File::open(self.path(name)?).await?;
+            let size = file.metadata().await?.len();
+            Ok(Object {
+                size,
+                body: ReaderStream::new(file).boxed(),
+            })
+        }
+        .boxed()
+    }
+
+    fn delete<'a>(&'a self, name: &'a str) -> BoxFuture<'a, io::Result<()>> {
+        async move { tokio::fs::remove_file(self.path(name)?).await }.boxed()
+    }
+
+    fn list(&self) -> BoxFuture<'_, io::Result<Vec<Entry>>> {
+        async move {
+            let mut entries = vec![];
+            // a store that never served has no directory yet.
+            if !tokio::fs::try_exists(&self.base).await? {
+                return Ok(entries);
+            }
+            if !self.shard {
+                read_files(&self.base, &mut entries).await?;
+                return Ok(entries);
+            }
+            for outer in read_subdirs(&self.base).await? {
+                for inner in read_subdirs(&outer).await? {
+                    read_files(&inner, &mut entries).await?;
+                }
+            }
+            Ok(entries)
+        }
+        .boxed()
+    }
+
+    fn stat<'a>(&'a self, name: &'a str) -> BoxFuture<'a, io::Result<Option<Entry>>> {
+        async move {
+            match tokio::fs::metadata(self.path(name)?).await {
+                Ok(meta) => to_entry(name.to_owned(), meta).map(Some),
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e),
+            }
+        }
+        .boxed()
+    }
+
+    fn local_dir(&self) -> Option<LocalDir<'_>> {
+        Some(LocalDir {
+            path: &self.base,
+            sharded: self.shard,
+        })
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright (c) 2026, Anthony DeDominic <adedomin@gmail.com>
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use axum::body::Bytes;
+#[cfg(feature = "serve-files")]
+use axum::extract::Request;
+use futures_util::{future::BoxFuture, stream::BoxStream};
+#[cfg(feature = "serve-files")]
+use http::Uri;
+use serde::Deserialize;
+
+pub mod fs;
+#[cfg(feature = "s3")]
+pub mod s3;
+
+/// An object in a store.
+pub struct Entry {
+    pub name: String,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+/// The contents of an object in a store.
+pub struct Object {
+    #[cfg_attr(not(feature = "serve-files"), allow(dead_code))]
+    pub size: u64,
+    pub body: BoxStream<'static, io::Result<Bytes>>,
+}
+
+/// Where a store keeps its uploads. Names are flat file names, e.g. "abc123.png".
+pub trait Backend: Send + Sync {
+    /// Copy a finished, staged upload into the store.
+    /// Must fail with [`io::ErrorKind::AlreadyExists`] instead of replacing an existing object.
+    fn put<'a>(&'a self, name: &'a str, staged: &'a Path) -> BoxFuture<'a, io::Result<()>>;
+
+    fn get<'a>(&'a self, name: &'a str) -> BoxFuture<'a, io::Result<Object>>;
+
+    /// Fails with [`io::ErrorKind::NotFound`] if there is no such object.
+    fn delete<'a>(&'a self, name: &'a str) -> BoxFuture<'a, io::Result<()>>;
+
+    fn list(&self) -> BoxFuture<'_, io::Result<Vec<Entry>>>;
+
+    fn stat<'a>(&'a self, name: &'a str) -> BoxFuture<'a, io::Result<Option<Entry>>>;
+
+    /// The local directory objects live in, if any, so they can be served without going through [`Backend::get`].
+    #[cfg_attr(not(feature = "serve-files"), allow(dead_code))]
+    fn local_dir(&self) -> Option<LocalDir<'_>> {
+        None
+    }
+}
+
+/// A directory a store's files can be served from directly.
+#[cfg_attr(not(feature = "serve-files"), allow(dead_code))]
+pub struct LocalDir<'a> {
+    pub path: &'a Path,
+    /// Files are under [`fs::shard_path`] instead of directly in path.
+    pub sharded: bool,
+}
+
+impl LocalDir<'_> {
+    /// Where name is in this directory.
+    pub fn file(&self, name: &str) -> PathBuf {
+        if self.sharded {
+            self.path.join(fs::shard_path(name))
+        } else {
+            self.path.join(name)
+        }
+    }
+}
+
+#[cfg(feature = "serve-files")]
+impl LocalDir<'_> {
+    /// Rewrites request paths from public names to where the files are in this directory.
+    pub fn map_request(&self) -> impl Fn(Request) -> Request + Clone + Send + Sync + 'static {
+        let sharded = self.sharded;
+        move |req| if sharded { shard_request(req) } else { req }
+    }
+}
+
+/// e.g. /abc123.png -> /a/b/abc123.png
+#[cfg(feature = "serve-files")]
+fn shard_request(mut req: Request) -> Request {
+    let Some(name) = req
+        .uri()
+        .path()
+        .strip_prefix('/')
+        .filter(|name| !name.is_empty() && !name.contains('/'))
+    else {
+        return req;
+    };
+    let path_and_query = match req.uri().query() {
+        Some(query) => format!("/{}?{query}", fs::shard_path(name)),
+        None => format!("/{}", fs::shard_path(name)),
+    };
+    let mut parts = req.uri().clone().into_parts();
+    parts.path_and_query = path_and_query.parse().ok();
+    if let Ok(uri) = Uri::from_parts(parts) {
+        *req.uri_mut() = uri;
+    }
+    req
+}
+
+/// Internally tagged, so serde_ignored never sees unknown keys in here; the variants deny them
+/// instead, which also means no "//" comments.
+#[derive(Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BackendSettings {
+    /// Files in the store's dir.
+    Fs(fs::FsSettings),
+    #[cfg(feature = "s3")]
+    S3(s3::S3Settings),
+}
+
+impl Default for BackendSettings {
+    fn default() -> Self {
+        Self::Fs(fs::FsSettings::default())
+    }
+}
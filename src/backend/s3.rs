@@ -0,0 +1,162 @@
+// Copyright (c) 2026, Anthony DeDominic <adedomin@gmail.com>
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+use std::{
+    io::{self, ErrorKind},
+    path::Path,
+    time::SystemTime,
+};
+
+use futures_util::{FutureExt, StreamExt, TryStreamExt, future::BoxFuture};
+use object_store::{
+    ObjectMeta, ObjectStore, PutMode, PutOptions,
+    aws::{AmazonS3, AmazonS3Builder},
+    path::Path as ObjPath,
+};
+use serde::Deserialize;
+
+use crate::backend::{Backend, Entry, Object};
+
+/// Credentials and region may also come from the usual AWS_* environment variables.
+/// Unknown keys are errors, see [`super::BackendSettings`].
+#[derive(Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct S3Settings {
+    bucket: String,
+    #[serde(default)]
+    prefix: String,
+    endpoint: Option<String>,
+    region: Option<String>,
+    access_key_id: Option<String>,
+    secret_access_key: Option<String>,
+    /// For local, MinIO-style stand-ins without TLS.
+    #[serde(default)]
+    allow_http: bool,
+}
+
+pub struct S3Backend {
+    store: AmazonS3,
+    prefix: ObjPath,
+}
+
+fn to_io(e: object_store::Error) -> io::Error {
+    match e {
+        object_store::Error::AlreadyExists { .. } | object_store::Error::Precondition { .. } => {
+            io::Error::new(ErrorKind::AlreadyExists, e)
+        }
+        e => e.into(),
+    }
+}
+
+impl TryFrom<S3Settings> for S3Backend {
+    type Error = io::Error;
+
+    fn try_from(s: S3Settings) -> Result<Self, Self::Error> {
+        let mut builder = AmazonS3Builder::from_env()
+            .with_bucket_name(s.bucket)
+            .with_allow_http(s.allow_http);
+        if let Some(endpoint) = s.endpoint {
+            builder = builder.with_endpoint(endpoint);
+        }
+        if let Some(region) = s.region {
+            builder = builder.with_region(region);
+        }
+        if let Some(key) = s.access_key_id {
+            builder = builder.with_access_key_id(key);
+        }
+        if let Some(secret) = s.secret_access_key {
+            builder = builder.with_secret_access_key(secret);
+        }
+        Ok(Self {
+            store: builder.build().map_err(to_io)?,
+            prefix: ObjPath::from(s.prefix),
+        })
+    }
+}
+
+impl S3Backend {
+    fn path(&self, name: &str) -> ObjPath {
+        self.prefix.child(name)
+    }
+}
+
+fn to_entry(meta: ObjectMeta) -> Option<Entry> {
+    Some(Entry {
+        name: meta.location.filename()?.to_owned(),
+        size: meta.size,
+        modified: SystemTime::from(meta.last_modified),
+    })
+}
+
+impl Backend for S3Backend {
+    fn put<'a>(&'a self, name: &'a str, staged: &'a Path) -> BoxFuture<'a, io::Result<()>> {
+        async move {
+            // uploads are bounded by the store's siz, so buffering them is fine.
+            let payload = tokio::fs::read(staged).await?;
+            let opts = PutOptions {
+                mode: PutMode::Create,
+                ..Default::default()
+            };
+            self.store
+                .put_opts(&self.path(name), payload.into(), opts)
+                .await
+                .map_err(to_io)?;
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn get<'a>(&'a self, name: &'a str) -> BoxFuture<'a, io::Result<Object>> {
+        async move {
+            let res = self.store.get(&self.path(name)).await.map_err(to_io)?;
+            Ok(Object {
+                size: res.meta.size,
+                body: res.into_stream().map_err(to_io).boxed(),
+            })
+        }
+        .boxed()
+    }
+
+    fn delete<'a>(&'a self, name: &'a str) -> BoxFuture<'a, io::Result<()>> {
+        async move {
+            // S3 happily deletes objects that don't exist.
+            let path = self.path(name);
+            self.store.head(&path).await.map_err(to_io)?;
+            self.store.delete(&path).await.map_err(to_io)
+        }
+        .boxed()
+    }
+
+    fn list(&self) -> BoxFuture<'_, io::Result<Vec<Entry>>> {
+        async move {
+            let res = self
+                .store
+                .list_with_delimiter(Some(&self.prefix))
+                .await
+                .map_err(to_io)?;
+            Ok(res.objects.into_iter().filter_map(to_entry).collect())
+        }
+        .boxed()
+    }
+
+    fn stat<'a>(&'a self, name: &'a str) -> BoxFuture<'a, io::Result<Option<Entry>>> {
+        async move {
+            match self.store.head(&self.path(name)).await {
+                Ok(meta) => Ok(to_entry(meta)),
+                Err(object_store::Error::NotFound { .. }) => Ok(None),
+                Err(e) => Err(to_io(e)),
+            }
+        }
+        .boxed()
+    }
+}
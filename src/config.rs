@@ -12,20 +12,41 @@
 // ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
 // OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
 use std::{
-    collections::VecDeque,
-    io::{self, ErrorKind},
+    collections::{BTreeMap, HashMap, HashSet},
+    ffi::OsString,
+    io::{self, ErrorKind, Write},
+    net::IpAddr,
     num::{NonZero, NonZeroUsize},
     path::{Path, PathBuf},
-    sync::{Arc, Mutex, atomic::AtomicU64},
+    sync::{
+        Arc, Mutex, OnceLock, RwLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+use hmac::{Hmac, Mac};
 use rand::{Rng, seq::SliceRandom};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use sqids::Sqids;
 
+#[cfg(feature = "s3")]
+use crate::backend::s3::S3Backend;
 use crate::{
+    backend::{
+        Backend, BackendSettings, Entry,
+        fs::{FsBackend, migrate_flat, shard_path},
+    },
     config::env_vars::{config, data, rt},
-    models::webdata::WebData,
+    models::{
+        dropfs::background_rm_file,
+        meta::{UploadMeta, unix_now},
+        mime::MEDIA,
+        strip::MAX_STRIP_SIZ,
+        validate::can_validate,
+        webdata::WebData,
+    },
 };
 
 #[cfg(unix)]
@@ -90,138 +111,1190 @@ pub enum ConfigError {
     DeserConfig(#[from] serde_json::Error),
     #[error("Config file not found at {0:?} - see example config below:\n\n{EXAMPLE_CONFIG}")]
     NoConfig(PathBuf),
+    #[error("Invalid store \"{0}\": {1}")]
+    InvalidStore(String, String),
 }
 
-const DEFAULT_DIR_BASE: [&str; 2] = ["i", "p"];
-const DEFAULT_SIZ_LIM: [usize; 2] = [10485760 /* 10MiB */, 65536 /* 64KiB */];
+/// What a store accepts.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    /// Images, videos and audio we can detect, see [`crate::models::mime::MEDIA`].
+    Media,
+    /// UTF-8 text.
+    Text,
+    /// Anything, always served as a download.
+    File,
+}
 
-fn siz_default<const T: usize>() -> NonZeroUsize {
-    NonZeroUsize::new(DEFAULT_SIZ_LIM[T]).unwrap()
+impl Kind {
+    fn default_siz(self) -> NonZeroUsize {
+        match self {
+            Kind::Media => NonZeroUsize::new(10485760 /* 10MiB */).unwrap(),
+            Kind::Text => NonZeroUsize::new(65536 /* 64KiB */).unwrap(),
+            Kind::File => NonZeroUsize::new(104857600 /* 100MiB */).unwrap(),
+        }
+    }
 }
 
-fn dir_default<const T: usize>() -> PathBuf {
-    find_systemd_or_xdg_path(data::BASE, data::USER, data::FALLBACK, DEFAULT_DIR_BASE[T])
+/// Defaults for the parts of a store that depend on which store it is.
+struct StoreDefaults<'a> {
+    kind: Kind,
+    route: String,
+    upload: String,
+    dir: &'a str,
 }
 
-#[derive(Deserialize)]
-struct StorageSettings<const T: usize> {
-    #[serde(default = "siz_default::<T>")]
-    siz: NonZeroUsize,
+impl<'a> StoreDefaults<'a> {
+    fn image() -> StoreDefaults<'static> {
+        StoreDefaults {
+            kind: Kind::Media,
+            route: "/i".to_owned(),
+            upload: "/upload".to_owned(),
+            dir: "i",
+        }
+    }
+
+    fn paste() -> StoreDefaults<'static> {
+        StoreDefaults {
+            kind: Kind::Text,
+            route: "/p".to_owned(),
+            upload: "/paste".to_owned(),
+            dir: "p",
+        }
+    }
+
+    fn named(name: &'a str) -> Self {
+        StoreDefaults {
+            kind: Kind::Media,
+            route: format!("/{name}"),
+            upload: format!("/upload/{name}"),
+            dir: name,
+        }
+    }
+}
+
+/// Whether uploaders may pick their own file names.
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Vanity {
+    #[default]
+    Off,
+    Allow,
+    /// Only for uploaders with one of the auth_tokens.
+    Auth,
+}
+
+/// Which uploads go first when a store is over cnt or max_bytes.
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Evict {
+    /// The oldest upload.
+    #[default]
+    Oldest,
+    /// The upload served least recently, or the oldest if it was never served.
+    Lru,
+}
+
+#[derive(Deserialize, Default, Clone, PartialEq)]
+struct StorageSettings {
+    kind: Option<Kind>,
+    /// Where uploads are served from, e.g. /i
+    route: Option<String>,
+    /// Where uploads are sent to, e.g. /upload
+    upload: Option<String>,
+    /// Extensions a media or file store takes, default: all of them.
+    accept: Option<Vec<String>>,
+    /// Extensions a file store refuses.
+    deny: Option<Vec<String>>,
+    siz: Option<NonZeroUsize>,
     cnt: Option<NonZeroUsize>,
-    #[serde(default = "dir_default::<T>")]
-    dir: PathBuf,
+    max_bytes: Option<NonZero<u64>>,
+    max_age: Option<NonZero<u64>>,
+    #[serde(default)]
+    dedup: bool,
+    min_free: Option<NonZero<u64>>,
+    #[serde(default)]
+    evict_for_space: bool,
+    #[serde(default)]
+    evict: Evict,
+    #[serde(default)]
+    compress: bool,
+    #[serde(default)]
+    vanity: Vanity,
+    /// Take resumable uploads (tus) for media stores.
+    #[serde(default)]
+    tus: bool,
+    /// Check the structure of media uploads, not just their first bytes.
+    #[serde(default)]
+    validate: bool,
+    /// Remove Exif, XMP and IPTC from images.
+    #[serde(default)]
+    strip_metadata: bool,
+    dir: Option<PathBuf>,
+    #[serde(default)]
+    backend: BackendSettings,
 }
 
-impl<const T: usize> Default for StorageSettings<T> {
-    fn default() -> Self {
-        Self {
-            siz: siz_default::<T>(),
-            cnt: None,
-            dir: dir_default::<T>(),
+/// Routes are matched literally, so keep them to plain path segments.
+fn check_route(route: &str) -> bool {
+    route.len() > 1
+        && route.starts_with('/')
+        && !route.ends_with('/')
+        && !route.contains("//")
+        && route[1..]
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || b"-_/".contains(&c))
+}
+
+/// Where the upload page and its assets are served from, see static_files::routes.
+const STATIC_ROUTES: [&str; 2] = ["/", "/public"];
+
+/// Whether route is, or is under, one of the [`STATIC_ROUTES`].
+fn is_static_route(route: &str) -> bool {
+    STATIC_ROUTES.iter().any(|r| {
+        route
+            .strip_prefix(r)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    })
+}
+
+/// The directories in dirs.
+fn subdirs(dirs: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    let mut subdirs = vec![];
+    for dir in dirs {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                subdirs.push(entry.path());
+            }
+        }
+    }
+    Ok(subdirs)
+}
+
+/// Internal state for a store lives next to it, hidden, so it is never served. e.g. i -> .i
+fn state_dir(dir: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(dir.file_name().unwrap_or_default());
+    dir.with_file_name(name)
+}
+
+const SECRET_LEN: usize = 32;
+const TOKEN_LEN: usize = 16;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Write to a temporary file and rename it over path, so a crash never leaves it half written.
+/// Only we can read it, like the secret [`load_or_create_secret`] makes.
+fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    // the mode only applies to new files, so don't reuse one left by a crash.
+    match std::fs::remove_file(&tmp) {
+        Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+        _ => (),
+    }
+    let mut opts = std::fs::OpenOptions::new();
+    opts.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut opts, 0o600);
+    opts.open(&tmp)?.write_all(contents)?;
+    std::fs::rename(&tmp, path)
+}
+
+fn load_or_create_secret(state: &Path) -> io::Result<[u8; SECRET_LEN]> {
+    let path = state.join("secret");
+    match std::fs::read(&path) {
+        Ok(secret) => secret.try_into().map_err(|_| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{path:?} is not a {SECRET_LEN} byte secret."),
+            )
+        }),
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            let mut secret = [0u8; SECRET_LEN];
+            rand::rng().fill(&mut secret);
+            let mut opts = std::fs::OpenOptions::new();
+            opts.write(true).create_new(true);
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut opts, 0o600);
+            opts.open(&path)?.write_all(&secret)?;
+            Ok(secret)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// The shuffled sqids alphabet must survive restarts or IDs would be minted from a new keyspace.
+fn load_or_create_idgen(state: &Path) -> io::Result<Sqids> {
+    let path = state.join("alphabet");
+    let alphabet = match std::fs::read_to_string(&path) {
+        Ok(alphabet) => alphabet.trim().chars().collect::<Vec<_>>(),
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            let mut rand_alpha = sqids::DEFAULT_ALPHABET.chars().collect::<Vec<_>>();
+            rand_alpha.shuffle(&mut rand::rng());
+            write_atomic(&path, rand_alpha.iter().collect::<String>().as_bytes())?;
+            rand_alpha
+        }
+        Err(e) => return Err(e),
+    };
+    Sqids::builder()
+        .alphabet(alphabet)
+        .build()
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, format!("{path:?}: {e}")))
+}
+
+/// What makes a store's names and deletion tokens its own, see [`StorageState::export_state`].
+const STATE_FILES: [&str; 3] = ["secret", "alphabet", "seqno"];
+
+/// Resumable uploads nobody touched for this long are thrown away.
+const TUS_EXPIRY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Sequence numbers are reserved on disk in blocks so they never repeat across restarts.
+const SEQ_BLOCK: u64 = 1024;
+
+struct SeqNo {
+    path: PathBuf,
+    next: u64,
+    reserved: u64,
+}
+
+impl SeqNo {
+    fn load(state: &Path) -> io::Result<Self> {
+        let path = state.join("seqno");
+        let next = match std::fs::read_to_string(&path) {
+            Ok(seq) => seq
+                .trim()
+                .parse::<u64>()
+                .map_err(|e| io::Error::new(ErrorKind::InvalidData, format!("{path:?}: {e}")))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => 0,
+            Err(e) => return Err(e),
+        };
+        // anything up to the last reservation may have been handed out already.
+        Ok(Self {
+            path,
+            next,
+            reserved: next,
+        })
+    }
+
+    fn next(&mut self) -> io::Result<u64> {
+        if self.next >= self.reserved {
+            let reserved = self.next + SEQ_BLOCK;
+            write_atomic(&self.path, reserved.to_string().as_bytes())?;
+            self.reserved = reserved;
+        }
+        self.next += 1;
+        Ok(self.next - 1)
+    }
+}
+
+/// An upload, with its metadata if it has any.
+pub struct Upload {
+    pub entry: Entry,
+    pub meta: Option<UploadMeta>,
+}
+
+impl Upload {
+    /// Seconds since the UNIX epoch. Files from before we kept metadata fall back to their mtime.
+    pub fn created(&self) -> u64 {
+        match &self.meta {
+            Some(meta) => meta.created,
+            None => self
+                .entry
+                .modified
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
         }
     }
 }
 
 pub struct StorageState {
-    base: PathBuf,
-    siz: NonZeroUsize,
-    stor: Option<Mutex<VecDeque<PathBuf>>>,
+    name: String,
+    kind: Kind,
+    route: String,
+    upload: String,
+    accept: Option<Vec<String>>,
+    deny: Option<Vec<String>>,
+    backend: Arc<dyn Backend>,
+    state: PathBuf,
+    /// Loaded or made by [`StorageState::create`], so merely opening a store writes nothing.
+    keys: OnceLock<Keys>,
+    /// Shared with the Content-Length check so a reload can change it.
+    siz: Arc<AtomicUsize>,
+    max_age: Option<Duration>,
+    stor: Option<Mutex<Fifo>>,
+    /// sha256 -> upload, when deduplication is on.
+    dedup: Option<Mutex<HashMap<String, String>>>,
+    min_free: Option<u64>,
+    evict_for_space: bool,
+    evict: Evict,
+    compress: bool,
+    vanity: Vanity,
+    tus: bool,
+    /// Resumable uploads with a PATCH in flight, so two requests never append to the same file.
+    tus_busy: Mutex<HashSet<String>>,
+    validate: bool,
+    strip_metadata: bool,
+    seqno: Mutex<SeqNo>,
+}
+
+/// What makes a store's names and deletion tokens its own.
+struct Keys {
+    secret: [u8; SECRET_LEN],
     idgen: Sqids,
-    seqno: AtomicU64,
 }
 
-fn push_inner(stor: &mut VecDeque<PathBuf>, new_path: PathBuf) -> Option<PathBuf> {
-    let mut ret = None;
-    if stor.len() == stor.capacity() {
-        ret = stor.pop_back();
-        stor.push_front(new_path);
-    } else {
-        stor.push_front(new_path);
+/// Uploads in the order they go, with their sizes so we can evict by count or total bytes.
+/// Each upload has a generation, bumped whenever it is uploaded or served; the lowest goes first.
+struct Fifo {
+    /// generation -> name
+    order: BTreeMap<u64, String>,
+    /// name -> (generation, size)
+    files: HashMap<String, (u64, u64)>,
+    next_gen: u64,
+    cnt: Option<usize>,
+    max_bytes: Option<u64>,
+    bytes: u64,
+}
+
+impl Fifo {
+    fn new(cnt: Option<usize>, max_bytes: Option<u64>) -> Self {
+        Self {
+            order: BTreeMap::new(),
+            files: HashMap::with_capacity(cnt.unwrap_or_default()),
+            next_gen: 0,
+            cnt,
+            max_bytes,
+            bytes: 0,
+        }
+    }
+
+    fn is_full(&self, incoming: u64) -> bool {
+        self.cnt.is_some_and(|cnt| self.files.len() >= cnt)
+            || self
+                .max_bytes
+                .is_some_and(|max| self.bytes.saturating_add(incoming) > max)
+    }
+
+    fn next_gen(&mut self) -> u64 {
+        self.next_gen += 1;
+        self.next_gen
+    }
+
+    /// Evict the oldest files until the new one fits, returns the evicted names.
+    fn push(&mut self, name: String, siz: u64) -> Vec<String> {
+        // the same name again, e.g. when re-importing; only the new one counts,
+        // and it mustn't push anything else out to make room for itself.
+        if let Some((old_gen, old_siz)) = self.files.remove(&name) {
+            self.order.remove(&old_gen);
+            self.bytes -= old_siz;
+        }
+        let mut evicted = vec![];
+        while self.is_full(siz)
+            && let Some((old, _)) = self.pop_oldest()
+        {
+            evicted.push(old);
+        }
+        let generation = self.next_gen();
+        self.bytes += siz;
+        self.order.insert(generation, name.clone());
+        self.files.insert(name, (generation, siz));
+        evicted
+    }
+
+    /// Move name to the front, as if it were just uploaded.
+    fn touch(&mut self, name: &str) {
+        let generation = self.next_gen();
+        if let Some((old_gen, _)) = self.files.get_mut(name)
+            && let Some(name) = self.order.remove(old_gen)
+        {
+            *old_gen = generation;
+            self.order.insert(generation, name);
+        }
+    }
+
+    fn pop_oldest(&mut self) -> Option<(String, u64)> {
+        let (_, old) = self.order.pop_first()?;
+        let (_, old_siz) = self.files.remove(&old)?;
+        self.bytes -= old_siz;
+        Some((old, old_siz))
+    }
+
+    fn retain<F: Fn(&str) -> bool>(&mut self, keep: F) {
+        let (bytes, order) = (&mut self.bytes, &mut self.order);
+        self.files.retain(|name, (generation, siz)| {
+            let keep = keep(name);
+            if !keep {
+                *bytes -= *siz;
+                order.remove(generation);
+            }
+            keep
+        });
     }
-    ret
 }
 
 impl StorageState {
-    pub fn get_base(&self) -> PathBuf {
-        self.base.clone()
+    #[cfg_attr(not(feature = "serve-files"), allow(dead_code))]
+    pub fn backend(&self) -> &dyn Backend {
+        self.backend.as_ref()
     }
 
     pub fn get_max_siz(&self) -> usize {
-        self.siz.get()
+        self.siz.load(Ordering::Relaxed)
+    }
+
+    /// The size limit as the Content-Length check sees it, changes on reload.
+    pub fn max_siz_handle(&self) -> Arc<AtomicUsize> {
+        self.siz.clone()
+    }
+
+    /// Whether new uploads are stored gzipped. Only pastes support this.
+    pub fn compress(&self) -> bool {
+        self.compress
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// Where uploads are served from, e.g. /i
+    pub fn route(&self) -> &str {
+        &self.route
+    }
+
+    /// Where uploads are sent to, e.g. /upload
+    pub fn upload_path(&self) -> &str {
+        &self.upload
+    }
+
+    pub fn accepts(&self, ext: &str) -> bool {
+        // a format we can't check could be anything.
+        (!self.validate || can_validate(ext))
+            && self
+                .accept
+                .as_ref()
+                .is_none_or(|accept| accept.iter().any(|a| a == ext))
+            && !self
+                .deny
+                .as_ref()
+                .is_some_and(|deny| deny.iter().any(|d| d == ext))
+    }
+
+    #[cfg_attr(not(feature = "serve-files"), allow(dead_code))]
+    pub fn evict(&self) -> Evict {
+        self.evict
     }
 
-    pub fn push<T: AsRef<Path>>(&self, new_path: T) -> Option<PathBuf> {
-        self.stor
+    /// Note that name was just served, so it is evicted last.
+    #[cfg_attr(not(feature = "serve-files"), allow(dead_code))]
+    pub fn touch(&self, name: &str) {
+        if self.evict == Evict::Lru
+            && let Some(mut stor) = self.stor.as_ref().map(|s| s.lock().unwrap())
+        {
+            stor.touch(name);
+        }
+    }
+
+    pub fn vanity(&self) -> Vanity {
+        self.vanity
+    }
+
+    pub fn get_max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+
+    /// Track a new upload of siz bytes, returns the files which must be deleted to make room.
+    fn push(&self, name: &str, siz: u64) -> Vec<String> {
+        let evicted = self
+            .stor
             .as_ref()
-            .map(|s| s.lock().unwrap())
-            .and_then(|mut stor| push_inner(&mut stor, new_path.as_ref().to_path_buf()))
+            .map(|s| s.lock().unwrap().push(name.to_owned(), siz))
+            .unwrap_or_default();
+        if let Some(mut dedup) = self.dedup.as_ref().map(|d| d.lock().unwrap()) {
+            dedup.retain(|_, n| !evicted.contains(n));
+        }
+        evicted
     }
 
-    fn prepopulate(&self) -> std::io::Result<()> {
-        let read_dir = match std::fs::read_dir(&self.base) {
-            Ok(r) => r,
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                std::fs::create_dir_all(&self.base)?;
-                // nothing to read, unless we have some kind of TOCTOU situation, which would be bizarre.
-                return Ok(());
+    /// Remember a freshly published upload, evicting old ones if we are over our limits.
+    pub async fn record(&self, name: &str, meta: &UploadMeta) {
+        if let Err(e) = self.write_meta(name, meta).await {
+            eprintln!("WARN: failed to write metadata for {name}: {e}");
+        }
+        if let Some(mut dedup) = self.dedup.as_ref().map(|d| d.lock().unwrap()) {
+            dedup.insert(meta.sha256.clone(), name.to_owned());
+        }
+        for del in self.push(name, meta.size) {
+            self.background_rm(del);
+        }
+    }
+
+    /// Make sure an upload of up to incoming bytes leaves at least min_free bytes on the volume.
+    /// If allowed, the oldest uploads are deleted to make room, otherwise this fails with
+    /// [`ErrorKind::StorageFull`].
+    pub async fn reserve_space(&self, incoming: u64) -> io::Result<()> {
+        let Some(min_free) = self.min_free else {
+            return Ok(());
+        };
+        let needed = min_free.saturating_add(incoming);
+        let mut avail = fs4::available_space(self.staging())?;
+        // deleting from a remote backend won't free anything here.
+        if self.evict_for_space && self.backend.local_dir().is_some() {
+            while avail < needed {
+                let mut deficit = needed - avail;
+                let mut evicted = vec![];
+                if let Some(mut stor) = self.stor.as_ref().map(|s| s.lock().unwrap()) {
+                    while deficit > 0
+                        && let Some((old, old_siz)) = stor.pop_oldest()
+                    {
+                        deficit = deficit.saturating_sub(old_siz);
+                        evicted.push(old);
+                    }
+                }
+                if evicted.is_empty() {
+                    break;
+                }
+                for del in evicted {
+                    // forget() would also drop it from the FIFO, which we already did.
+                    if let Some(mut dedup) = self.dedup.as_ref().map(|d| d.lock().unwrap()) {
+                        dedup.retain(|_, n| *n != del);
+                    }
+                    _ = tokio::fs::remove_file(self.meta_path(&del)).await;
+                    match self.backend.delete(&del).await {
+                        Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+                        _ => (),
+                    }
+                }
+                avail = fs4::available_space(self.staging())?;
             }
-            Err(e) => return Err(e),
+        }
+        if avail < needed {
+            return Err(io::Error::new(
+                ErrorKind::StorageFull,
+                "Not enough free space for this upload.",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Find an existing upload with the same contents, returns its name.
+    pub async fn find_dup(&self, sha256: &str) -> Option<String> {
+        let name = self.dedup.as_ref()?.lock().unwrap().get(sha256)?.clone();
+        if let Ok(Some(_)) = self.backend.stat(&name).await {
+            Some(name)
+        } else {
+            // removed behind our back.
+            self.forget(&name);
+            None
+        }
+    }
+
+    fn staging(&self) -> PathBuf {
+        self.state.join("tmp")
+    }
+
+    /// Resumable uploads, unlike staging, survive restarts.
+    pub fn tus_dir(&self) -> PathBuf {
+        self.state.join("tus")
+    }
+
+    pub fn validates(&self) -> bool {
+        self.validate
+    }
+
+    pub fn strips_metadata(&self) -> bool {
+        self.strip_metadata
+    }
+
+    /// Claim the resumable upload id for writing, false if another request already has it.
+    pub fn claim_tus(&self, id: &str) -> bool {
+        self.tus_busy.lock().unwrap().insert(id.to_owned())
+    }
+
+    /// Let others write to the resumable upload id again, see [`StorageState::claim_tus`].
+    pub fn release_tus(&self, id: &str) {
+        self.tus_busy.lock().unwrap().remove(id);
+    }
+
+    /// Where resumable uploads are created, if this store takes them. e.g. /upload/tus
+    pub fn tus_path(&self) -> Option<String> {
+        self.tus.then(|| format!("{}/tus", self.upload))
+    }
+
+    fn meta_dir(&self) -> PathBuf {
+        self.state.join("meta")
+    }
+
+    /// Sidecars are sharded like the files of a sharded store, so neither directory grows unbounded.
+    fn sharded(&self) -> bool {
+        self.backend.local_dir().is_some_and(|dir| dir.sharded)
+    }
+
+    fn meta_path(&self, name: &str) -> PathBuf {
+        let file = format!("{name}.json");
+        if self.sharded() {
+            self.meta_dir().join(shard_path(&file))
+        } else {
+            self.meta_dir().join(file)
+        }
+    }
+
+    pub async fn write_meta(&self, name: &str, meta: &UploadMeta) -> io::Result<()> {
+        let meta = serde_json::to_vec(meta)?;
+        let path = self.meta_path(name);
+        if self.sharded()
+            && let Some(parent) = path.parent()
+        {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, meta).await
+    }
+
+    pub async fn read_meta(&self, name: &str) -> io::Result<UploadMeta> {
+        let meta = tokio::fs::read(self.meta_path(name)).await?;
+        Ok(serde_json::from_slice(&meta)?)
+    }
+
+    /// Delete an upload and its metadata without waiting on it.
+    pub fn background_rm(&self, del: String) {
+        background_rm_file(self.meta_path(&del));
+        // it's already out of the FIFO, but identical uploads mustn't link to it anymore.
+        if let Some(mut dedup) = self.dedup.as_ref().map(|d| d.lock().unwrap()) {
+            dedup.retain(|_, n| *n != del);
+        }
+        let backend = self.backend.clone();
+        tokio::spawn(async move {
+            _ = backend.delete(&del).await;
+        });
+    }
+
+    /// Delete an upload and its metadata, and stop tracking it.
+    pub async fn delete(&self, name: &str) -> io::Result<()> {
+        _ = tokio::fs::remove_file(self.meta_path(name)).await;
+        let res = self.backend.delete(name).await;
+        self.forget(name);
+        res
+    }
+
+    /// Salted so the stored hash can't be reversed by hashing every IPv4 address.
+    pub fn hash_ip(&self, ip: IpAddr) -> String {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.keys().secret).expect("HMAC takes any key size.");
+        mac.update(b"ip:");
+        mac.update(ip.to_canonical().to_string().as_bytes());
+        hex::encode(&mac.finalize().into_bytes()[..TOKEN_LEN])
+    }
+
+    /// Everything in the store, with its metadata if it has any.
+    pub async fn uploads(&self) -> io::Result<Vec<Upload>> {
+        let mut uploads = vec![];
+        for entry in self.backend.list().await? {
+            let meta = self.read_meta(&entry.name).await.ok();
+            uploads.push(Upload { entry, meta });
+        }
+        Ok(uploads)
+    }
+
+    /// The effective settings, one per line.
+    pub fn describe(&self) -> String {
+        let or = |v: Option<String>, none: &str| v.unwrap_or_else(|| none.to_owned());
+        let (cnt, max_bytes) = self
+            .stor
+            .as_ref()
+            .map(|s| {
+                let s = s.lock().unwrap();
+                (s.cnt, s.max_bytes)
+            })
+            .unwrap_or_default();
+        let dir = match self.backend.local_dir() {
+            Some(dir) if dir.sharded => format!("{} (sharded)", dir.path.display()),
+            Some(dir) => dir.path.display().to_string(),
+            None => "remote".to_owned(),
         };
-        if let Some(mut stor) = self.stor.as_ref().map(|s| s.lock().unwrap()) {
-            for file in read_dir {
-                let file = file?.path();
-                if file.is_file()
-                    && let Some(del) = push_inner(&mut stor, file)
+        [
+            format!("kind: {:?}", self.kind),
+            format!("route: {}", self.route),
+            format!("upload: {}", self.upload),
+            format!("dir: {dir}"),
+            format!("state: {}", self.state.display()),
+            format!("siz: {}", self.get_max_siz()),
+            format!("cnt: {}", or(cnt.map(|v| v.to_string()), "unlimited")),
+            format!(
+                "max_bytes: {}",
+                or(max_bytes.map(|v| v.to_string()), "unlimited")
+            ),
+            format!(
+                "max_age: {}",
+                or(self.max_age.map(|v| format!("{}s", v.as_secs())), "forever")
+            ),
+            format!(
+                "min_free: {}",
+                or(self.min_free.map(|v| v.to_string()), "none")
+            ),
+            format!("evict_for_space: {}", self.evict_for_space),
+            format!("evict: {:?}", self.evict),
+            format!("dedup: {}", self.dedup.is_some()),
+            format!("compress: {}", self.compress),
+            format!("vanity: {:?}", self.vanity),
+            format!("tus: {}", self.tus),
+            format!("validate: {}", self.validate),
+            format!("strip_metadata: {}", self.strip_metadata),
+            format!(
+                "accept: {}",
+                or(self.accept.as_ref().map(|a| a.join(", ")), "all")
+            ),
+            format!(
+                "deny: {}",
+                or(self.deny.as_ref().map(|d| d.join(", ")), "none")
+            ),
+        ]
+        .join("\n")
+    }
+
+    /// Names of all uploads we have metadata for, whether or not they still exist.
+    pub fn meta_names(&self) -> io::Result<Vec<String>> {
+        // a store that never served has no metadata.
+        if !self.meta_dir().try_exists()? {
+            return Ok(vec![]);
+        }
+        let mut dirs = vec![self.meta_dir()];
+        if self.sharded() {
+            for _ in 0..2 {
+                dirs = subdirs(&dirs)?;
+            }
+        }
+        let mut names = vec![];
+        for dir in dirs {
+            for meta in std::fs::read_dir(dir)? {
+                let meta = meta?;
+                if meta.file_type()?.is_file()
+                    && let Some(name) = meta.path().file_stem()
                 {
-                    std::fs::remove_file(del)?;
+                    names.push(name.to_string_lossy().into_owned());
+                }
+            }
+        }
+        Ok(names)
+    }
+
+    /// Create whatever is missing for the store to take uploads: its directories, secret and
+    /// sqids alphabet. Only serving and import do this, other admin commands leave a store as it is.
+    pub fn create(&self) -> io::Result<()> {
+        if let Some(dir) = self.backend.local_dir() {
+            std::fs::create_dir_all(dir.path)?;
+        }
+        std::fs::create_dir_all(self.staging())?;
+        if self.tus {
+            std::fs::create_dir_all(self.tus_dir())?;
+        }
+        std::fs::create_dir_all(self.meta_dir())?;
+        if self.keys.get().is_none() {
+            let keys = Keys {
+                secret: load_or_create_secret(&self.state)?,
+                idgen: load_or_create_idgen(&self.state)?,
+            };
+            _ = self.keys.set(keys);
+        }
+        Ok(())
+    }
+
+    fn keys(&self) -> &Keys {
+        self.keys
+            .get()
+            .expect("stores are created before they take uploads.")
+    }
+
+    /// The contents of the state files, so another instance can take over this store's names.
+    pub fn export_state(&self) -> io::Result<Vec<(&'static str, Vec<u8>)>> {
+        let mut state = vec![];
+        for name in STATE_FILES {
+            match std::fs::read(self.state.join(name)) {
+                Ok(contents) => state.push((name, contents)),
+                // e.g. there is no seqno before the first upload.
+                Err(e) if e.kind() == ErrorKind::NotFound => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(state)
+    }
+
+    /// Replace a state file from [`StorageState::export_state`].
+    /// This only takes effect on the next start.
+    pub fn import_state(&self, name: &str, contents: &[u8]) -> io::Result<()> {
+        if !STATE_FILES.contains(&name) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("unknown state file {name}"),
+            ));
+        }
+        write_atomic(&self.state.join(name), contents)
+    }
+
+    async fn prepopulate(&self) -> std::io::Result<()> {
+        // anything left in staging is from uploads interrupted by a crash or restart.
+        match std::fs::remove_dir_all(self.staging()) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+            _ => self.create()?,
+        }
+        if let Some(dir) = self.backend.local_dir()
+            && dir.sharded
+        {
+            migrate_flat(dir.path)?;
+            migrate_flat(&self.meta_dir())?;
+        }
+        let mut uploads = vec![];
+        for upload in self.uploads().await? {
+            let created = upload.created();
+            if let Some(meta) = upload.meta
+                && let Some(mut dedup) = self.dedup.as_ref().map(|d| d.lock().unwrap())
+            {
+                dedup.insert(meta.sha256, upload.entry.name.clone());
+            }
+            uploads.push((created, upload.entry.name, upload.entry.size));
+        }
+        // drop metadata for files that were removed while we weren't running.
+        let names: HashSet<&str> = uploads.iter().map(|(_, n, _)| n.as_str()).collect();
+        for name in self.meta_names()? {
+            if !names.contains(name.as_str()) {
+                std::fs::remove_file(self.meta_path(&name))?;
+            }
+        }
+        uploads.sort_unstable();
+        let mut evicted = vec![];
+        if let Some(mut stor) = self.stor.as_ref().map(|s| s.lock().unwrap()) {
+            for (_, name, siz) in uploads {
+                evicted.extend(stor.push(name, siz));
+            }
+        }
+        for del in evicted {
+            self.delete(&del).await?;
+        }
+        Ok(())
+    }
+
+    /// Stop tracking a file that was deleted.
+    pub fn forget(&self, name: &str) {
+        if let Some(mut stor) = self.stor.as_ref().map(|s| s.lock().unwrap()) {
+            stor.retain(|n| n != name);
+        }
+        if let Some(mut dedup) = self.dedup.as_ref().map(|d| d.lock().unwrap()) {
+            dedup.retain(|_, n| n != name);
+        }
+    }
+
+    /// Names can be taken again after a delete or eviction, so the token covers this upload's
+    /// contents and time too, not just its name.
+    fn token_mac(&self, fname: &str, meta: &UploadMeta) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.keys().secret).expect("HMAC takes any key size.");
+        mac.update(fname.as_bytes());
+        mac.update(b"\0");
+        mac.update(meta.sha256.as_bytes());
+        mac.update(&meta.created.to_be_bytes());
+        mac
+    }
+
+    /// A token the uploader can use to delete fname later, as long as it is this upload.
+    pub fn deletion_token(&self, fname: &str, meta: &UploadMeta) -> String {
+        hex::encode(&self.token_mac(fname, meta).finalize().into_bytes()[..TOKEN_LEN])
+    }
+
+    /// Uploads without metadata can't be deleted with a token, only by an admin.
+    pub async fn check_deletion_token(&self, fname: &str, token: &str) -> bool {
+        let Ok(meta) = self.read_meta(fname).await else {
+            return false;
+        };
+        match hex::decode(token) {
+            Ok(token) if token.len() == TOKEN_LEN => self
+                .token_mac(fname, &meta)
+                .verify_truncated_left(&token)
+                .is_ok(),
+            _ => false,
+        }
+    }
+
+    /// Whether this store keeps track of its uploads in memory, see [`StorageState::reconcile`].
+    pub fn tracks_uploads(&self) -> bool {
+        self.stor.is_some() || self.dedup.is_some()
+    }
+
+    /// Forget uploads whose files were deleted behind our back, e.g. by an admin or tmpfiles.d,
+    /// so they stop counting against cnt and max_bytes. Returns how many were forgotten.
+    pub async fn reconcile(&self) -> io::Result<usize> {
+        let mut tracked = HashSet::new();
+        if let Some(stor) = self.stor.as_ref().map(|s| s.lock().unwrap()) {
+            tracked.extend(stor.files.keys().cloned());
+        }
+        if let Some(dedup) = self.dedup.as_ref().map(|d| d.lock().unwrap()) {
+            tracked.extend(dedup.values().cloned());
+        }
+        // only what was tracked before listing, uploads finishing meanwhile aren't listed.
+        let listed: HashSet<String> = self
+            .backend
+            .list()
+            .await?
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        let gone: HashSet<String> = tracked
+            .into_iter()
+            .filter(|name| !listed.contains(name))
+            .collect();
+        if gone.is_empty() {
+            return Ok(0);
+        }
+        if let Some(mut stor) = self.stor.as_ref().map(|s| s.lock().unwrap()) {
+            stor.retain(|n| !gone.contains(n));
+        }
+        if let Some(mut dedup) = self.dedup.as_ref().map(|d| d.lock().unwrap()) {
+            dedup.retain(|_, n| !gone.contains(n));
+        }
+        for name in &gone {
+            background_rm_file(self.meta_path(name));
+        }
+        Ok(gone.len())
+    }
+
+    /// Remove files older than max_age and drop them from the FIFO.
+    pub async fn reap(&self) -> std::io::Result<()> {
+        let Some(max_age) = self.max_age else {
+            return Ok(());
+        };
+        // by when they were uploaded, like purge, so imported files keep their age.
+        let cutoff = unix_now().saturating_sub(max_age.as_secs());
+        for upload in self.uploads().await? {
+            // created in the future (clock skew) is treated as brand new.
+            if upload.created() < cutoff {
+                match self.delete(&upload.entry.name).await {
+                    Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+                    _ => (),
                 }
             }
         }
         Ok(())
     }
 
-    pub fn gen_new_fname(&self, ext: &'static str) -> String {
+    /// Remove resumable uploads which haven't been touched in a while, finished or not.
+    pub async fn expire_tus(&self) -> io::Result<()> {
+        if !self.tus {
+            return Ok(());
+        }
+        // an upload is its data and an info file, e.g. <id> and <id>.json; keep both or neither.
+        let mut touched: HashMap<String, SystemTime> = HashMap::new();
+        let mut files = vec![];
+        let mut dir = tokio::fs::read_dir(self.tus_dir()).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            let modified = entry.metadata().await?.modified()?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let id = name.split('.').next().unwrap_or_default().to_owned();
+            let last = touched.entry(id.clone()).or_insert(modified);
+            *last = modified.max(*last);
+            files.push((id, entry.path()));
+        }
+        let now = SystemTime::now();
+        for (id, path) in files {
+            if now.duration_since(touched[&id]).unwrap_or_default() > TUS_EXPIRY {
+                match tokio::fs::remove_file(path).await {
+                    Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+                    _ => (),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn gen_new_fname(&self, ext: &str) -> io::Result<String> {
         for _ in 0..64 {
-            let seq = self
-                .seqno
-                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            let seq = self.seqno.lock().unwrap().next()?;
             // pads out the id for low sequence numbers and adds minor random noise to it.
             let rand_junk = rand::rng().random::<u16>() as u64;
             // unlikely, but it could fail to generate an ID due to offensive words.
-            if let Ok(id) = self.idgen.encode(&[seq, rand_junk]) {
-                return format!("{id}.{ext}",);
+            if let Ok(id) = self.keys().idgen.encode(&[seq, rand_junk]) {
+                return Ok(format!("{id}.{ext}",));
             }
         }
         panic!("Failed to generate an ID after 64 attempts. Something is wrong.");
     }
+
+    /// Create a file in the staging area. Nothing in staging is publicly served.
+    pub async fn create_staging(&self) -> io::Result<(PathBuf, tokio::fs::File)> {
+        loop {
+            let staged = self
+                .staging()
+                .join(format!("{:016x}", rand::rng().random::<u64>()));
+            match tokio::fs::File::options()
+                .write(true)
+                .create_new(true)
+                .open(&staged)
+                .await
+            {
+                Ok(file) => return Ok((staged, file)),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Move a finished upload out of staging under a new, unique name.
+    pub async fn publish(&self, staged: &Path, ext: &str) -> io::Result<String> {
+        for _ in 0..64 {
+            let fname = self.gen_new_fname(ext)?;
+            match self.backend.put(&fname, staged).await {
+                Ok(()) => {
+                    _ = tokio::fs::remove_file(staged).await;
+                    return Ok(fname);
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Err(io::Error::new(
+            ErrorKind::AlreadyExists,
+            "Failed to find a free file name after 64 attempts.",
+        ))
+    }
+
+    /// Like [`StorageState::publish`], but under a name the uploader picked.
+    /// Fails with [`ErrorKind::AlreadyExists`] if it is taken.
+    pub async fn publish_as(&self, staged: &Path, fname: &str) -> io::Result<()> {
+        self.backend.put(fname, staged).await?;
+        _ = tokio::fs::remove_file(staged).await;
+        Ok(())
+    }
 }
 
-impl<const T: usize> From<StorageSettings<T>> for StorageState {
-    fn from(value: StorageSettings<T>) -> Self {
-        let stor = value
-            .cnt
-            .map(|v| v.get())
-            .map(|cap| Mutex::new(VecDeque::with_capacity(cap)));
+impl StorageState {
+    fn new(
+        name: &str,
+        value: StorageSettings,
+        defaults: StoreDefaults,
+    ) -> Result<Self, ConfigError> {
+        let invalid = |why: &str| ConfigError::InvalidStore(name.to_owned(), why.to_owned());
+        let kind = value.kind.unwrap_or(defaults.kind);
+        let route = value.route.unwrap_or(defaults.route);
+        let upload = value.upload.unwrap_or(defaults.upload);
+        if !check_route(&route) || !check_route(&upload) {
+            return Err(invalid(
+                "route and upload must be paths like /a/b made of a-z, A-Z, 0-9, - and _.",
+            ));
+        }
+        if let Some(path) = [&route, &upload].into_iter().find(|p| is_static_route(p)) {
+            return Err(invalid(&format!(
+                "{path} is taken by the upload page and its files."
+            )));
+        }
+        // file extensions are compared in lowercase.
+        let lower = |exts: Option<Vec<String>>| {
+            exts.map(|exts| exts.iter().map(|ext| ext.to_ascii_lowercase()).collect())
+        };
+        let accept: Option<Vec<String>> = lower(value.accept);
+        let deny: Option<Vec<String>> = lower(value.deny);
+        match (kind, &accept) {
+            (Kind::Text, Some(_)) => return Err(invalid("text stores don't take accept.")),
+            (Kind::Media, Some(accept)) => {
+                if let Some(ext) = accept
+                    .iter()
+                    .find(|ext| !MEDIA.iter().any(|(known, _)| known == ext))
+                {
+                    return Err(invalid(&format!("can't detect {ext} files.")));
+                }
+            }
+            _ => (),
+        }
+        if deny.is_some() && kind != Kind::File {
+            return Err(invalid("only file stores take deny."));
+        }
+        if value.compress && kind != Kind::Text {
+            return Err(invalid("only text stores can be compressed."));
+        }
+        if value.tus && kind != Kind::Media {
+            return Err(invalid("only media stores take resumable uploads."));
+        }
+        if value.validate && kind != Kind::Media {
+            return Err(invalid("only media stores can be validated."));
+        }
+        if value.validate
+            && let Some(ext) = accept.iter().flatten().find(|ext| !can_validate(ext))
+        {
+            return Err(invalid(&format!(
+                "validate can't check {ext} files, leave them out of accept."
+            )));
+        }
+        if value.strip_metadata && kind != Kind::Media {
+            return Err(invalid("only media stores can strip metadata."));
+        }
+        if value.strip_metadata && value.siz.is_some_and(|siz| siz.get() > MAX_STRIP_SIZ) {
+            return Err(invalid(&format!(
+                "siz can be at most {MAX_STRIP_SIZ} with strip_metadata."
+            )));
+        }
+        let dir = value.dir.unwrap_or_else(|| {
+            find_systemd_or_xdg_path(data::BASE, data::USER, data::FALLBACK, defaults.dir)
+        });
 
-        let mut rng = rand::rng();
-        let mut rand_alpha = sqids::DEFAULT_ALPHABET.chars().collect::<Vec<_>>();
-        rand_alpha.shuffle(&mut rng);
-        let idgen = Sqids::builder()
-            .alphabet(rand_alpha)
-            .build()
-            .expect("Should not happen. Alphabet is from the crate, but shuffled.");
+        let cnt = value.cnt.map(|v| v.get());
+        let max_bytes = value.max_bytes.map(|v| v.get());
+        // evicting for space needs to know which files are the oldest, even without other limits.
+        let stor = (cnt.is_some() || max_bytes.is_some() || value.evict_for_space)
+            .then(|| Mutex::new(Fifo::new(cnt, max_bytes)));
+        if value.evict == Evict::Lru && stor.is_none() {
+            return Err(invalid(
+                "evict only matters with cnt, max_bytes or evict_for_space.",
+            ));
+        }
+        if value.evict == Evict::Lru && !cfg!(feature = "serve-files") {
+            return Err(invalid(
+                "evict: lru needs the serve-files feature to see which files are served.",
+            ));
+        }
 
-        Self {
-            base: value.dir,
-            siz: value.siz,
+        // nothing is created here, see StorageState::create.
+        let state = state_dir(&dir);
+        let seqno = Mutex::new(SeqNo::load(&state)?);
+        let backend: Arc<dyn Backend> = match value.backend {
+            BackendSettings::Fs(fs) => Arc::new(FsBackend::new(dir, fs)),
+            #[cfg(feature = "s3")]
+            BackendSettings::S3(s3) => Arc::new(S3Backend::try_from(s3)?),
+        };
+
+        Ok(Self {
+            name: name.to_owned(),
+            kind,
+            route,
+            upload,
+            accept,
+            deny,
+            backend,
+            state,
+            keys: OnceLock::new(),
+            siz: Arc::new(AtomicUsize::new(
+                value.siz.unwrap_or(kind.default_siz()).get(),
+            )),
+            max_age: value.max_age.map(|nz| Duration::from_secs(nz.get())),
             stor,
-            idgen,
-            seqno: AtomicU64::new(0),
-        }
+            dedup: value.dedup.then(|| Mutex::new(HashMap::new())),
+            min_free: value.min_free.map(|nz| nz.get()),
+            evict_for_space: value.evict_for_space,
+            evict: value.evict,
+            compress: value.compress,
+            vanity: value.vanity,
+            tus: value.tus,
+            tus_busy: Mutex::new(HashSet::new()),
+            validate: value.validate,
+            strip_metadata: value.strip_metadata,
+            seqno,
+        })
     }
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, PartialEq)]
 pub struct Ratelim {
     pub secs: Option<NonZero<u64>>,
     pub burst: Option<NonZero<u32>>,
@@ -259,13 +1332,24 @@ fn bind_default() -> String {
 
 #[derive(Deserialize)]
 pub struct Config {
-    image: Option<StorageSettings<0>>,
-    paste: Option<StorageSettings<1>>,
+    image: Option<StorageSettings>,
+    paste: Option<StorageSettings>,
+    /// More stores, by name.
+    #[serde(default)]
+    stores: BTreeMap<String, StorageSettings>,
     pub ratelim: Option<Ratelim>,
     #[serde(default)]
     pub link_prefix: String,
     #[serde(default = "bind_default")]
     bind: String,
+    #[serde(default)]
+    auth_tokens: Vec<String>,
+    /// Keys we don't know, likely typos.
+    #[serde(skip)]
+    unknown: Vec<String>,
+    /// Where this was read from, so it can be read again.
+    #[serde(skip)]
+    path: PathBuf,
 }
 
 const PORT_ENV: [&str; 3] = ["HTTP_PLATFORM_PORT", "FUNCTIONS_CUSTOMHANDLER_PORT", "8146"];
@@ -291,23 +1375,177 @@ impl Config {
         }
     }
 
-    fn is_unix_listener(&self) -> bool {
+    /// Keys in the config we don't know, e.g. image.cnnt
+    pub fn unknown_keys(&self) -> &[String] {
+        &self.unknown
+    }
+
+    pub fn auth_token_count(&self) -> usize {
+        self.auth_tokens.len()
+    }
+
+    pub fn is_unix_listener(&self) -> bool {
         self.get_bind_addr().strip_prefix("unix:").is_some()
     }
 
-    pub fn get_webdata(&mut self) -> Result<Arc<WebData>, ConfigError> {
-        let image = StorageState::from(self.image.take().unwrap_or_default());
-        let paste = StorageState::from(self.paste.take().unwrap_or_default());
-        image.prepopulate()?;
-        paste.prepopulate()?;
+    /// Set up every store without touching what's in them, e.g. for admin commands.
+    /// Nothing is created or migrated until [`StorageState::create`] and prepopulate.
+    pub fn get_stores(&self) -> Result<Vec<StorageState>, ConfigError> {
+        let mut stores = vec![
+            StorageState::new(
+                "image",
+                self.image.clone().unwrap_or_default(),
+                StoreDefaults::image(),
+            )?,
+            StorageState::new(
+                "paste",
+                self.paste.clone().unwrap_or_default(),
+                StoreDefaults::paste(),
+            )?,
+        ];
+        for (name, settings) in self.stores.clone() {
+            // the name is the default route and directory.
+            if name.is_empty()
+                || !name.starts_with(|c: char| c.is_ascii_alphanumeric())
+                || !name
+                    .bytes()
+                    .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_')
+            {
+                return Err(ConfigError::InvalidStore(
+                    name,
+                    "names must be made of a-z, A-Z, 0-9, - and _.".to_owned(),
+                ));
+            }
+            if stores.iter().any(|s| s.name == name) {
+                return Err(ConfigError::InvalidStore(
+                    name,
+                    "image and paste are already taken.".to_owned(),
+                ));
+            }
+            let store = StorageState::new(&name, settings, StoreDefaults::named(&name))?;
+            stores.push(store);
+        }
+        let paths = |s: &StorageState| {
+            [Some(s.route.clone()), Some(s.upload.clone()), s.tus_path()]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+        };
+        for (i, store) in stores.iter().enumerate() {
+            if let Some(other) = stores[..i].iter().find(|other| {
+                other.state == store.state || paths(other).iter().any(|p| paths(store).contains(p))
+            }) {
+                return Err(ConfigError::InvalidStore(
+                    store.name.clone(),
+                    format!(
+                        "shares a route, upload or tus path, or dir with \"{}\".",
+                        other.name
+                    ),
+                ));
+            }
+        }
+        Ok(stores)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Apply what can change while serving from a freshly read config.
+    /// Returns the settings that differ but need a restart to take effect.
+    pub fn apply_live(&mut self, new: Config, webdata: &WebData) -> Vec<String> {
+        let mut fixed = vec![];
+        if self.bind != new.bind {
+            fixed.push("bind".to_owned());
+        }
+        if self.auth_tokens != new.auth_tokens {
+            fixed.push("auth_tokens".to_owned());
+        }
+        match (&self.ratelim, new.ratelim) {
+            (Some(_), Some(ratelim)) => self.ratelim = Some(ratelim),
+            (None, None) => (),
+            _ => fixed.push("turning ratelim on or off".to_owned()),
+        }
+        *webdata.link_prefix.write().unwrap() = new.link_prefix.clone();
+        self.link_prefix = new.link_prefix;
+
+        let without_siz = |s: &StorageSettings| StorageSettings {
+            siz: None,
+            ..s.clone()
+        };
+        let mut new_stores = new.stores;
+        new_stores.insert("image".to_owned(), new.image.unwrap_or_default());
+        new_stores.insert("paste".to_owned(), new.paste.unwrap_or_default());
+        for store in &webdata.stores {
+            let key = match store.name() {
+                name @ ("image" | "paste") => name.to_owned(),
+                name => format!("stores.{name}"),
+            };
+            let old = match store.name() {
+                "image" => self.image.get_or_insert_default(),
+                "paste" => self.paste.get_or_insert_default(),
+                name => self
+                    .stores
+                    .get_mut(name)
+                    .expect("every running store came from the config."),
+            };
+            let Some(new) = new_stores.remove(store.name()) else {
+                fixed.push(format!("removing {key}"));
+                continue;
+            };
+            if without_siz(old) != without_siz(&new) {
+                fixed.push(format!("{key} settings other than siz"));
+            }
+            let siz = new.siz.unwrap_or(store.kind().default_siz());
+            if store.strips_metadata() && siz.get() > MAX_STRIP_SIZ {
+                fixed.push(format!(
+                    "{key}.siz over {MAX_STRIP_SIZ} with strip_metadata"
+                ));
+                continue;
+            }
+            store.siz.store(siz.get(), Ordering::Relaxed);
+            old.siz = new.siz;
+        }
+        for name in new_stores.into_keys() {
+            fixed.push(format!("adding stores.{name}"));
+        }
+        fixed
+    }
+
+    pub async fn get_webdata(&self) -> Result<Arc<WebData>, ConfigError> {
+        let stores = self.get_stores()?;
+        for store in &stores {
+            store.prepopulate().await?;
+        }
         Ok(Arc::new(WebData {
-            image,
-            paste,
-            link_prefix: self.link_prefix.clone(),
+            stores,
+            link_prefix: RwLock::new(self.link_prefix.clone()),
+            auth_tokens: self
+                .auth_tokens
+                .iter()
+                .map(|token| Sha256::digest(token).into())
+                .collect(),
         }))
     }
 }
 
+/// e.g. stores.logs.siz
+fn key_path(path: &serde_ignored::Path) -> String {
+    use serde_ignored::Path;
+    match path {
+        Path::Root => String::new(),
+        Path::Map {
+            parent: Path::Root,
+            key,
+        } => key.clone(),
+        Path::Map { parent, key } => format!("{}.{key}", key_path(parent)),
+        Path::Seq { parent, index } => format!("{}[{index}]", key_path(parent)),
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => key_path(parent),
+    }
+}
+
 pub fn open_and_parse<T>(config_path: T) -> Result<Config, ConfigError>
 where
     T: std::fmt::Debug + AsRef<Path>,
@@ -315,7 +1553,17 @@ where
     match std::fs::File::open(&config_path) {
         Ok(file) => {
             let file = std::io::BufReader::new(file);
-            Ok(serde_json::from_reader(file)?)
+            let mut de = serde_json::Deserializer::from_reader(file);
+            let mut unknown = vec![];
+            let mut config: Config = serde_ignored::deserialize(&mut de, |path| {
+                // "//" keys are comments.
+                if !matches!(&path, serde_ignored::Path::Map { key, .. } if key.starts_with("//")) {
+                    unknown.push(key_path(&path));
+                }
+            })?;
+            de.end()?;
+            config.unknown = unknown;
+            Ok(config)
         }
         Err(e) if e.kind() == ErrorKind::NotFound => {
             Err(ConfigError::NoConfig(config_path.as_ref().to_path_buf()))
@@ -324,14 +1572,18 @@ where
     }
 }
 
-pub fn get_config() -> Result<(Config, Arc<WebData>), ConfigError> {
-    let config = std::env::args_os()
-        .nth(1)
-        .map(PathBuf::from)
-        .unwrap_or_else(|| {
-            find_systemd_or_xdg_path(config::BASE, config::USER, config::FALLBACK, "config.json")
-        });
-    let mut config = open_and_parse(config)?;
+/// The given config path, or the default location.
+pub fn config_path(path: Option<PathBuf>) -> PathBuf {
+    path.unwrap_or_else(|| {
+        find_systemd_or_xdg_path(config::BASE, config::USER, config::FALLBACK, "config.json")
+    })
+}
+
+/// Read the config at path, or the default location.
+pub fn load_config(path: Option<PathBuf>) -> Result<Config, ConfigError> {
+    let path = config_path(path);
+    let mut config = open_and_parse(&path)?;
+    config.path = path;
     // fixup ratelim config in unix socket case.
     if config.is_unix_listener()
         && let Some(ratelim) = config.ratelim.as_mut()
@@ -340,27 +1592,121 @@ pub fn get_config() -> Result<(Config, Arc<WebData>), ConfigError> {
         eprintln!("WARN: ratelim.trust_headers must be true when using a unix listener!");
         ratelim.trust_headers = Some(true);
     }
-    let webdata = config.get_webdata()?;
+    Ok(config)
+}
+
+pub async fn get_config(path: Option<PathBuf>) -> Result<(Config, Arc<WebData>), ConfigError> {
+    let config = load_config(path)?;
+    for key in config.unknown_keys() {
+        eprintln!("WARN: unknown config key {key}, see check-config.");
+    }
+    let webdata = config.get_webdata().await?;
     Ok((config, webdata))
 }
 
 const EXAMPLE_CONFIG: &str = r###"
 { "image":
-    { "//": "Max allowed image size in bytes. default 10MiB."
+    { "//": "Only take some of the formats we detect. default: all of them."
+    , "accept": ["png", "jpg", "gif", "webp", "webm", "mp4"]
+    , "//": "Max allowed image size in bytes. default 10MiB."
     , "siz": 10485760
     , "//": "Max number of files before deleting. default: unlimited."
     , "cnt": 100
+    , "//": "Max total size of all files in bytes before deleting the oldest. default: unlimited."
+    , "max_bytes": 1073741824
+    , "//": "Max age of files in seconds before deleting. default: forever."
+    , "max_age": 604800
+    , "//": "Return the existing link when someone uploads an identical file. default: false."
+    , "dedup": true
+    , "//": "Reject uploads with 507 Insufficient Storage if they would leave less than this many bytes free. default: no limit."
+    , "min_free": 1073741824
+    , "//": "Instead of rejecting, delete the oldest uploads until there is room. Only for local storage. default: false."
+    , "evict_for_space": false
+    , "//": "Which uploads the limits above delete first. one of: oldest, lru. default: oldest."
+    , "//": "lru keeps files people still look at, but only sees requests that reach imageshare-rs,"
+    , "//": "needs the serve-files feature, and starts over from upload times on restart."
+    , "evict": "oldest"
+    , "//": "Let uploaders pick a name with X-Vanity-Name: deploy-diagram -> /i/deploy-diagram.png"
+    , "//": "Names are 3 to 64 of a-z, A-Z, 0-9, - and _ and can't be taken. one of: off, allow, auth. default: off."
+    , "//": "auth only allows uploaders sending one of auth_tokens as \"Authorization: Bearer <token>\"."
+    , "vanity": "auth"
+    , "//": "Take resumable uploads with the tus protocol (https://tus.io, 1.0 core and creation) at <upload>/tus,"
+    , "//": "for large videos over unreliable connections. Unfinished uploads are kept for a day. default: false."
+    , "tus": true
+    , "//": "Parse uploads to the end and reject malformed, truncated or padded files with 422,"
+    , "//": "e.g. a PNG with a zip or HTML appended. png, apng, jpg, gif, webp, bmp, webm, mkv, ogg, opus, ogv, wav"
+    , "//": "and the mp4 family (mp4, m4v, m4a, mov, 3gp, 3g2, heic, heif, avif) are checked, other formats"
+    , "//": "are refused and can't be in accept. default: false."
+    , "validate": true
+    , "//": "Remove Exif (e.g. GPS coordinates and camera serial numbers), XMP and IPTC from jpg, png, webp,"
+    , "//": "heic and avif uploads without re-encoding them. The Exif orientation is kept so photos stay upright."
+    , "//": "Anything after the end of a jpg, like the extra images of a Motion Photo, is dropped too."
+    , "//": "Uploads are stripped in memory, so siz can be at most 64MiB. default: false."
+    , "strip_metadata": true
+    , "//": "Uploads are staged in a hidden sibling of dir (e.g. .i), which must be on the same filesystem."
     , "//": "Path to store images in, default uses ${STATE_DIRECTORY}/i or ${XDG_DATA_HOME}/${CARGO_PKG_NAME}/i"
     , "dir": "./uploads/i"
+    , "//": "Where uploads are kept. default: { \"type\": \"fs\" }, i.e. in dir."
+    , "//": "With { \"type\": \"fs\", \"shard\": true }, files go in prefix directories, e.g. dir/a/b/abc123.png."
+    , "//": "Names of one character are padded with _, e.g. dir/a/_/a.png. Metadata in .i/meta is sharded the same way."
+    , "//": "Use this for very large stores. Existing files are moved into place on startup; this can't be undone."
+    , "//": "With the s3 feature, uploads may be kept in an S3-compatible bucket instead; dir still holds state."
+    , "//": "Unknown keys in backend are errors rather than warnings, so it can't hold // comments."
+    , "//backend":
+        { "type": "s3"
+        , "bucket": "imageshare"
+        , "prefix": "i"
+        , "endpoint": "http://localhost:9000"
+        , "region": "us-east-1"
+        , "access_key_id": "minioadmin"
+        , "secret_access_key": "minioadmin"
+        , "allow_http": true
+        }
     }
 , "paste":
     { "//": "Max allowed paste size. Note: pastes are buffered in memory to check for utf8-ness and simplicity."
     , "siz": 65536
     , "//": "Max number of files before deleting. default: unlimited."
     , "cnt": 10000
+    , "//": "Max total size of all files in bytes before deleting the oldest. default: unlimited."
+    , "max_bytes": 104857600
+    , "//": "Max age of files in seconds before deleting. default: forever."
+    , "max_age": 604800
+    , "//": "Store pastes gzipped. They are sent as is to clients accepting gzip, and decompressed for others."
+    , "//": "Pastes are then always served by imageshare, so proxy /p to it instead of serving the files directly."
+    , "//": "Turning this off later doesn't decompress existing pastes, so keep proxying /p then. default: false."
+    , "compress": true
+    , "vanity": "off"
     , "//": "Path to store images in, default uses ${STATE_DIRECTORY}/p or ${XDG_DATA_HOME}/${CARGO_PKG_NAME}/p"
     , "dir": "./uploads/p"
     }
+, "//": "More stores, by name. They take the same settings as image and paste, plus:"
+, "//": "kind:   media (like image), text (like paste) or file. default: media."
+, "//": "        file stores take anything, named by the extension of an X-File-Name header, or .bin without one."
+, "//": "        they are always served as application/octet-stream downloads. accept and deny limit extensions."
+, "//": "route:  where uploads are served from. default: /<name>. / and /public belong to the upload page."
+, "//": "upload: where uploads are POSTed to. default: /upload/<name>"
+, "//": "dir defaults to ${STATE_DIRECTORY}/<name> or ${XDG_DATA_HOME}/${CARGO_PKG_NAME}/<name>"
+, "//": "image (/i, /upload) and paste (/p, /paste) always exist and may set route and upload too."
+, "stores":
+    { "screenshots":
+        { "accept": ["png", "jpg"]
+        , "route": "/s"
+        , "cnt": 1000
+        }
+    , "logs":
+        { "kind": "text"
+        , "siz": 1048576
+        , "compress": true
+        , "max_age": 86400
+        }
+    , "files":
+        { "kind": "file"
+        , "route": "/f"
+        , "deny": ["exe", "msi", "scr", "bat", "cmd", "ps1"]
+        , "max_age": 604800
+        }
+    }
 , "ratelim":
     { "//": "Number of seconds to restore one token."
     , "secs": 30
@@ -371,6 +1717,8 @@ const EXAMPLE_CONFIG: &str = r###"
     , "//": "Max number of IPs to track. Fixed size. default: 16384 (~128KiB of state)."
     , "bucket_size": 16384
     }
+, "//": "Tokens for trusted uploaders, see vanity. default: none."
+, "auth_tokens": ["change-me-to-something-long-and-random"]
 , "//": "the path prepended to upload results"
 , "link_prefix": "http://localhost:8146"
 , "bind": "127.0.0.1:8146"
@@ -386,3 +1734,89 @@ const EXAMPLE_CONFIG: &str = r###"
 , "//bind": "rt-dir:web.sock"
 }
 "###;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push(fifo: &mut Fifo, name: &str, siz: u64) -> Vec<String> {
+        fifo.push(name.to_owned(), siz)
+    }
+
+    /// Empty it, oldest first.
+    fn drain(fifo: &mut Fifo) -> Vec<String> {
+        std::iter::from_fn(|| fifo.pop_oldest().map(|(name, _)| name)).collect()
+    }
+
+    #[test]
+    fn evicts_by_count() {
+        let mut fifo = Fifo::new(Some(2), None);
+        assert!(push(&mut fifo, "a", 1).is_empty());
+        assert!(push(&mut fifo, "b", 1).is_empty());
+        assert_eq!(push(&mut fifo, "c", 1), ["a"]);
+        assert_eq!(push(&mut fifo, "d", 1), ["b"]);
+        assert_eq!(drain(&mut fifo), ["c", "d"]);
+        assert_eq!(fifo.bytes, 0);
+    }
+
+    #[test]
+    fn evicts_by_bytes() {
+        let mut fifo = Fifo::new(None, Some(10));
+        assert!(push(&mut fifo, "a", 4).is_empty());
+        assert!(push(&mut fifo, "b", 4).is_empty());
+        assert_eq!(push(&mut fifo, "c", 5), ["a"]);
+        assert_eq!(fifo.bytes, 9);
+        assert_eq!(push(&mut fifo, "d", 10), ["b", "c"]);
+        assert_eq!(fifo.bytes, 10);
+        // larger than the quota on its own, so everything else goes.
+        assert_eq!(push(&mut fifo, "e", 11), ["d"]);
+        assert_eq!(fifo.bytes, 11);
+        assert_eq!(drain(&mut fifo), ["e"]);
+    }
+
+    #[test]
+    fn same_name_counts_once() {
+        let mut fifo = Fifo::new(Some(2), Some(100));
+        push(&mut fifo, "a", 10);
+        push(&mut fifo, "b", 10);
+        assert!(push(&mut fifo, "a", 20).is_empty());
+        assert_eq!(fifo.bytes, 30);
+        assert_eq!(fifo.order.len(), 2);
+        // it is the newest now.
+        assert_eq!(push(&mut fifo, "c", 1), ["b"]);
+        assert_eq!(fifo.bytes, 21);
+        assert_eq!(drain(&mut fifo), ["a", "c"]);
+    }
+
+    #[test]
+    fn touch_reorders() {
+        let mut fifo = Fifo::new(Some(3), None);
+        push(&mut fifo, "a", 1);
+        push(&mut fifo, "b", 1);
+        push(&mut fifo, "c", 1);
+        fifo.touch("a");
+        fifo.touch("b");
+        fifo.touch("a");
+        fifo.touch("gone");
+        assert_eq!(push(&mut fifo, "d", 1), ["c"]);
+        assert_eq!(fifo.order.len(), 3);
+        assert_eq!(fifo.bytes, 3);
+        assert_eq!(drain(&mut fifo), ["b", "a", "d"]);
+    }
+
+    #[test]
+    fn retain_keeps_bytes() {
+        let mut fifo = Fifo::new(None, Some(10));
+        push(&mut fifo, "a", 1);
+        push(&mut fifo, "b", 2);
+        push(&mut fifo, "c", 4);
+        fifo.retain(|name| name != "b");
+        assert_eq!(fifo.bytes, 5);
+        assert_eq!(fifo.order.len(), 2);
+        assert_eq!(push(&mut fifo, "d", 6), ["a"]);
+        assert_eq!(fifo.bytes, 10);
+        fifo.retain(|_| false);
+        assert_eq!(fifo.bytes, 0);
+        assert!(fifo.order.is_empty());
+    }
+}
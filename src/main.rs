@@ -14,21 +14,53 @@
 use std::process::exit;
 
 use crate::{
+    admin::AdminErr,
     config::{ConfigError, get_config},
     web::WebErr,
 };
+mod admin;
+mod backend;
 mod config;
 mod middleware;
 mod models;
+mod reaper;
 mod shutdown;
 mod web;
 
+const USAGE: &str = r###"usage: imageshare-rs [ config.json ] [ command ]
+
+commands, instead of serving:
+  list [ store ]                          list uploads, oldest first
+  rm <store> <file>...                    delete uploads
+  purge --older-than <age> [ store ]      delete uploads older than age, e.g. 3600, 45m, 12h or 30d
+  stats [ store ]                         count uploads and their size
+  verify [ store ]                        check uploads against their metadata
+  check-config                            print the effective config and check it for mistakes
+  export <store> <archive.tar>            write uploads, their order and metadata to an archive
+  import <store> <archive.tar>            restore an export, keeping every name
+
+An export holds the store's secret, which signs deletion tokens and hashes uploader IPs.
+Keep archives as private as the store itself."###;
+
 #[derive(thiserror::Error, Debug)]
 enum MainErr {
-    #[error("{0}\n\nusage: imageshare-rs [ config.json ]")]
+    #[error("{0}\n\n{USAGE}")]
     Cfg(#[from] ConfigError),
     #[error("{0}")]
     Web(#[from] WebErr),
+    #[error("{0}\n\n{USAGE}")]
+    Usage(AdminErr),
+    #[error("{0}")]
+    Admin(AdminErr),
+}
+
+impl From<AdminErr> for MainErr {
+    fn from(e: AdminErr) -> Self {
+        match e {
+            AdminErr::Usage(_) => Self::Usage(e),
+            _ => Self::Admin(e),
+        }
+    }
 }
 
 #[cfg(unix)]
@@ -40,15 +72,20 @@ fn main() {
 }
 
 fn real_main() -> Result<(), MainErr> {
-    let (config, webdata) = get_config()?;
+    let (config_path, cmd) = admin::parse_args(std::env::args_os().skip(1))?;
     let rt = tokio::runtime::Builder::new_current_thread()
         .enable_io()
+        .enable_time()
         .build()
         .unwrap();
-    Ok(rt.block_on(async {
+    rt.block_on(async {
+        if let Some(cmd) = cmd {
+            return Ok(admin::run(cmd, config_path).await?);
+        }
+        let (config, webdata) = get_config(config_path).await?;
         let web = web::start_web(config, webdata);
-        web.await.unwrap()
-    })?)
+        Ok(web.await.unwrap()?)
+    })
 }
 
 #[cfg(windows)]
@@ -0,0 +1,96 @@
+// Copyright (c) 2026, Anthony DeDominic <adedomin@gmail.com>
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use axum::{extract::Request, response::Response};
+use http::{
+    HeaderValue,
+    header::{CONTENT_DISPOSITION, CONTENT_TYPE, X_CONTENT_TYPE_OPTIONS},
+};
+use pin_project_lite::pin_project;
+use tower::{Layer, Service};
+
+pin_project! {
+    pub struct AttachmentFut<I> {
+        #[pin]
+        inner: I
+    }
+}
+
+impl<I, E, ResBody> Future for AttachmentFut<I>
+where
+    I: Future<Output = Result<Response<ResBody>, E>>,
+{
+    type Output = Result<Response<ResBody>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        match this.inner.poll(cx) {
+            // errors are our own JSON, leave them be.
+            Poll::Ready(Ok(mut res))
+                if res.status().is_success() || res.status().is_redirection() =>
+            {
+                let headers = res.headers_mut();
+                headers.insert(
+                    CONTENT_TYPE,
+                    HeaderValue::from_static("application/octet-stream"),
+                );
+                headers.insert(CONTENT_DISPOSITION, HeaderValue::from_static("attachment"));
+                headers.insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+                Poll::Ready(Ok(res))
+            }
+            other => other,
+        }
+    }
+}
+
+/// A Tower Layer that makes browsers download files instead of displaying them.
+#[derive(Clone)]
+pub struct Attachment;
+
+/// A Tower Service that makes browsers download files instead of displaying them.
+#[derive(Clone)]
+pub struct AttachmentService<S> {
+    inner: S,
+}
+
+impl<S> Layer<S> for Attachment {
+    type Service = AttachmentService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Self::Service { inner }
+    }
+}
+
+impl<S, ResBody> Service<Request> for AttachmentService<S>
+where
+    S: Service<Request, Response = Response<ResBody>> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = AttachmentFut<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let inner = self.inner.call(req);
+        AttachmentFut { inner }
+    }
+}
@@ -11,39 +11,74 @@
 // WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
 // ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
 // OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
-use std::task::{Context, Poll};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    task::{Context, Poll},
+};
 
 use axum::{
     extract::Request,
     response::{IntoResponse, Response},
 };
-use http::{HeaderMap, StatusCode, header::CONTENT_LENGTH};
+use http::{
+    HeaderMap, StatusCode,
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
+};
 use tower::{Layer, Service};
 
 use crate::{middleware::earlyretfut::EarlyRetFut, models::api::ApiError};
 
 /// A Tower Layer that checks HTTP Header Content-Length and rejects requests that are too large.
+/// The limit is shared so it can be changed on reload.
 #[derive(Clone)]
-pub struct HeaderSizeLim(usize);
+pub struct HeaderSizeLim {
+    siz: Arc<AtomicUsize>,
+    parts: usize,
+}
+
+impl From<Arc<AtomicUsize>> for HeaderSizeLim {
+    fn from(value: Arc<AtomicUsize>) -> Self {
+        Self {
+            siz: value,
+            parts: 1,
+        }
+    }
+}
 
-impl From<usize> for HeaderSizeLim {
-    fn from(value: usize) -> Self {
-        Self(value)
+impl HeaderSizeLim {
+    /// multipart/form-data requests may carry up to this many files of the limit each.
+    pub fn with_form_parts(self, parts: usize) -> Self {
+        Self { parts, ..self }
     }
 }
 
+pub fn is_form(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("multipart/form-data"))
+}
+
 /// A Tower Service that checks HTTP Header Content-Length and rejects requests that are too large.
 #[derive(Clone)]
 pub struct HeaderSizeLimMiddle<S> {
     inner: S,
-    siz: usize,
+    siz: Arc<AtomicUsize>,
+    parts: usize,
 }
 
 impl<S> Layer<S> for HeaderSizeLim {
     type Service = HeaderSizeLimMiddle<S>;
 
     fn layer(&self, inner: S) -> Self::Service {
-        Self::Service { inner, siz: self.0 }
+        Self::Service {
+            inner,
+            siz: self.siz.clone(),
+            parts: self.parts,
+        }
     }
 }
 
@@ -81,8 +116,9 @@ where
     fn call(&mut self, req: Request) -> Self::Future {
         // only non-safe should have *any* body.
         if !req.method().is_safe() {
-            let lim = self.siz;
             let headers = req.headers();
+            let parts = if is_form(headers) { self.parts } else { 1 };
+            let lim = self.siz.load(Ordering::Relaxed).saturating_mul(parts);
             if !check_len(headers, lim) {
                 return EarlyRetFut::new_early(
                     ApiError::new_with_status(
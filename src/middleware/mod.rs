@@ -11,9 +11,10 @@
 // WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
 // ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
 // OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+pub mod attachment;
 pub mod contentlen;
 pub mod csrf;
 pub mod earlyretfut;
 pub mod ratelim;
 #[cfg(feature = "serve-files")]
-pub mod utf8textplain;
+pub mod touch;
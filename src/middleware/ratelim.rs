@@ -25,7 +25,7 @@ use http::{StatusCode, header::RETRY_AFTER};
 use std::{
     hash::{BuildHasher, RandomState},
     net::{IpAddr, SocketAddr},
-    sync::Arc,
+    sync::{Arc, RwLock},
     task::{Context, Poll},
 };
 use tower::{Layer, Service};
@@ -36,15 +36,21 @@ use crate::{
     models::api::{ApiError, JSON_TYPE},
 };
 
-#[derive(Clone)]
+/// The client IP the ratelimiter settled on, for handlers that want to know who uploaded.
+#[derive(Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
+
 struct BucketRateLimState {
     trust_headers: bool,
-    ratelim: Arc<RateLimiter<IpAddr, BucketStateStore, MonotonicClock>>,
+    ratelim: RateLimiter<IpAddr, BucketStateStore, MonotonicClock>,
 }
 
+/// Swapped whole on reload, requests in flight keep the one they started with.
+type SharedState = Arc<RwLock<Arc<BucketRateLimState>>>;
+
 #[derive(Clone)]
 pub struct BucketRatelim {
-    state: BucketRateLimState,
+    state: SharedState,
 }
 
 pub struct BucketStateStore(RandomState, Vec<InMemoryState>);
@@ -69,7 +75,7 @@ impl StateStore for BucketStateStore {
     }
 }
 
-impl From<Ratelim> for BucketRatelim {
+impl From<Ratelim> for BucketRateLimState {
     fn from(rl: Ratelim) -> Self {
         let quota = Quota::with_period(rl.secs())
             .expect("ratelim config is always nonzero.")
@@ -81,18 +87,31 @@ impl From<Ratelim> for BucketRatelim {
                 .collect(),
         );
         Self {
-            state: BucketRateLimState {
-                trust_headers: rl.trust_headers(),
-                ratelim: Arc::new(RateLimiter::new(quota, state, MonotonicClock)),
-            },
+            trust_headers: rl.trust_headers(),
+            ratelim: RateLimiter::new(quota, state, MonotonicClock),
+        }
+    }
+}
+
+impl From<Ratelim> for BucketRatelim {
+    fn from(rl: Ratelim) -> Self {
+        Self {
+            state: Arc::new(RwLock::new(Arc::new(rl.into()))),
         }
     }
 }
 
+impl BucketRatelim {
+    /// Replace the limits; every client starts over with a full burst.
+    pub fn reload(&self, rl: Ratelim) {
+        *self.state.write().unwrap() = Arc::new(rl.into());
+    }
+}
+
 #[derive(Clone)]
 pub struct BucketRatelimMiddle<S> {
     inner: S,
-    state: BucketRateLimState,
+    state: SharedState,
 }
 
 impl<S> Layer<S> for BucketRatelim {
@@ -119,14 +138,15 @@ where
         self.inner.poll_ready(cx)
     }
 
-    fn call(&mut self, req: Request) -> Self::Future {
+    fn call(&mut self, mut req: Request) -> Self::Future {
+        let state = self.state.read().unwrap().clone();
         let get_ip = || {
             req.extensions()
                 .get::<ConnectInfo<SocketAddr>>()
                 .map(|f| f.ip())
         };
         #[cfg(unix)]
-        let ip = if self.state.trust_headers {
+        let ip = if state.trust_headers {
             req.headers()
                 .get("X-Real-IP")
                 .and_then(|hv| hv.to_str().ok())
@@ -137,7 +157,7 @@ where
         // IIS HttpPlatformHandler is... different.
         // we use X-Forwarded-For, but we have to parse the IP as a SocketAddr
         #[cfg(windows)]
-        let ip = if self.state.trust_headers {
+        let ip = if state.trust_headers {
             req.headers()
                 .get("x-forwarded-for")
                 .and_then(|hv| hv.to_str().ok())
@@ -160,9 +180,9 @@ where
             );
         };
 
-        if let Err(not_until) = self.state.ratelim.check_key(&ip) {
+        if let Err(not_until) = state.ratelim.check_key(&ip) {
             // bit weird... especially since NotUntil has a private field start.
-            let start = self.state.ratelim.clock().now();
+            let start = state.ratelim.clock().now();
             let retry_after = not_until.wait_time_from(start).as_secs();
 
             EarlyRetFut::new_early(
@@ -179,6 +199,7 @@ where
                 req.into_body().into_data_stream(),
             )
         } else {
+            req.extensions_mut().insert(ClientIp(ip));
             EarlyRetFut::new_next(self.inner.call(req))
         }
     }
@@ -0,0 +1,105 @@
+// Copyright (c) 2026, Anthony DeDominic <adedomin@gmail.com>
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use axum::{extract::Request, response::Response};
+use http::{Method, StatusCode};
+use pin_project_lite::pin_project;
+use tower::{Layer, Service};
+
+use crate::{config::Evict, models::webdata::StoreData};
+
+pin_project! {
+    pub struct TouchFut<I> {
+        #[pin]
+        inner: I,
+        hit: Option<(StoreData, String)>,
+    }
+}
+
+impl<I, E, ResBody> Future for TouchFut<I>
+where
+    I: Future<Output = Result<Response<ResBody>, E>>,
+{
+    type Output = Result<Response<ResBody>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = std::task::ready!(this.inner.poll(cx));
+        if let Ok(res) = &res
+            // a 304 is still someone looking at it.
+            && (res.status().is_success() || res.status() == StatusCode::NOT_MODIFIED)
+            && let Some((store, name)) = this.hit.take()
+        {
+            store.storage().touch(&name);
+        }
+        Poll::Ready(res)
+    }
+}
+
+/// A Tower Layer that tells a store which of its files are served, for least recently used eviction.
+#[derive(Clone)]
+pub struct Touch(StoreData);
+
+impl Touch {
+    /// Only stores evicting the least recently used care about hits.
+    pub fn lru(store: &StoreData) -> Option<Self> {
+        (store.storage().evict() == Evict::Lru).then(|| Self(store.clone()))
+    }
+}
+
+/// A Tower Service that tells a store which of its files are served.
+#[derive(Clone)]
+pub struct TouchService<S> {
+    inner: S,
+    store: StoreData,
+}
+
+impl<S> Layer<S> for Touch {
+    type Service = TouchService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Self::Service {
+            inner,
+            store: self.0.clone(),
+        }
+    }
+}
+
+impl<S, ResBody> Service<Request> for TouchService<S>
+where
+    S: Service<Request, Response = Response<ResBody>> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = TouchFut<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let hit = matches!(*req.method(), Method::GET | Method::HEAD)
+            .then(|| req.uri().path().rsplit('/').next())
+            .flatten()
+            .filter(|name| !name.is_empty())
+            .map(|name| (self.store.clone(), name.to_owned()));
+        let inner = self.inner.call(req);
+        TouchFut { inner, hit }
+    }
+}
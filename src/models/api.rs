@@ -13,13 +13,15 @@
 // OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
 use std::fmt::Display;
 
-use axum::{extract::rejection::StringRejection, response::IntoResponse};
+use axum::response::IntoResponse;
 use http::{
     HeaderName, HeaderValue, Response, StatusCode,
     header::{CONNECTION, CONTENT_TYPE},
 };
 use serde::Serialize;
 
+use crate::models::validate::ValidateErr;
+
 #[derive(Serialize, Debug)]
 pub struct ApiError {
     #[serde(skip)]
@@ -28,6 +30,8 @@ pub struct ApiError {
     close: bool,
     status: &'static str,
     msg: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delete_token: Option<String>,
 }
 
 const FALLBACK: &[u8] = br##"{ "status": "critical", "msg": "failed to serialize api message." }"##;
@@ -42,6 +46,7 @@ impl ApiError {
             close: false,
             status: "error",
             msg: msg.to_string(),
+            delete_token: None,
         }
     }
 
@@ -51,6 +56,7 @@ impl ApiError {
             close: false,
             status: "ok",
             msg: msg.to_string(),
+            delete_token: None,
         }
     }
 
@@ -60,6 +66,14 @@ impl ApiError {
             close: false,
             status: if code.is_success() { "ok" } else { "error" },
             msg: msg.to_string(),
+            delete_token: None,
+        }
+    }
+
+    pub fn with_delete_token(self, token: String) -> Self {
+        Self {
+            delete_token: Some(token),
+            ..self
         }
     }
 
@@ -78,6 +92,10 @@ impl ApiError {
         Self { close, ..self }
     }
 
+    pub fn closes_conn(&self) -> bool {
+        self.close
+    }
+
     pub fn to_json(&self) -> Vec<u8> {
         match serde_json::to_vec(&self) {
             Ok(ok) => ok,
@@ -86,19 +104,72 @@ impl ApiError {
     }
 }
 
-impl From<StringRejection> for ApiError {
-    fn from(value: StringRejection) -> Self {
-        ApiError::new_with_status(value.status(), value)
+impl From<axum::Error> for ApiError {
+    fn from(e: axum::Error) -> Self {
+        ApiError::new(e)
+    }
+}
+
+impl From<multer::Error> for ApiError {
+    fn from(e: multer::Error) -> Self {
+        let code = match e {
+            multer::Error::FieldSizeExceeded { .. } | multer::Error::StreamSizeExceeded { .. } => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            _ => StatusCode::BAD_REQUEST,
+        };
+        // there's no telling where the next part starts.
+        ApiError::new_with_status(code, e).close_conn()
+    }
+}
+
+impl From<ValidateErr> for ApiError {
+    fn from(e: ValidateErr) -> Self {
+        match e {
+            ValidateErr::Io(e) => e.into(),
+            e => ApiError::new_with_status(StatusCode::UNPROCESSABLE_ENTITY, e),
+        }
     }
 }
 
 impl From<std::io::Error> for ApiError {
     fn from(e: std::io::Error) -> Self {
+        if e.kind() == std::io::ErrorKind::StorageFull {
+            return Self::new("Not enough free space for your upload, try again later.")
+                .status(StatusCode::INSUFFICIENT_STORAGE)
+                .close_conn();
+        }
         eprintln!("ERR: unexpected I/O error: {e}");
         Self::new(e).close_conn()
     }
 }
 
+/// The results of an upload with several files, in the order they were sent.
+pub struct ApiResults(pub Vec<ApiError>);
+
+impl IntoResponse for ApiResults {
+    fn into_response(self) -> axum::response::Response {
+        // if anything was stored, the client has to look at each result anyway.
+        let code = self
+            .0
+            .iter()
+            .find(|r| r.code.is_success())
+            .or(self.0.first())
+            .map(|r| r.code)
+            .unwrap_or(StatusCode::UNPROCESSABLE_ENTITY);
+        let res = Response::builder()
+            .status(code)
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        let res = if self.0.iter().any(|r| r.close) {
+            res.header(CONNECTION, "close")
+        } else {
+            res
+        };
+        let body = serde_json::to_vec(&self.0).unwrap_or_else(|_| FALLBACK.to_owned());
+        res.body(body.into()).unwrap()
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
         let res = Response::builder()
@@ -0,0 +1,77 @@
+// Copyright (c) 2026, Anthony DeDominic <adedomin@gmail.com>
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//! Minimal files for tests, built a byte at a time.
+
+/// The start of a zip, e.g. appended to an image to make a polyglot.
+pub const ZIP: &[u8] = b"PK\x03\x04\x14\0\0\0\x08\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0";
+pub const HTML: &[u8] = b"<html><script>alert(document.domain)</script></html>";
+
+/// 1x1, 8-bit grayscale.
+pub const IHDR: [u8; 13] = [0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0];
+/// The one pixel of [`IHDR`], deflated.
+pub const IDAT: &[u8] = &[0x78, 0x9c, 0x63, 0x60, 0, 0, 0, 2, 0, 1];
+
+/// A PNG chunk with its CRC.
+pub fn png_chunk(typ: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+    chunk.extend_from_slice(typ);
+    chunk.extend_from_slice(data);
+    chunk.extend_from_slice(&crc32fast::hash(&chunk[4..]).to_be_bytes());
+    chunk
+}
+
+/// The PNG signature and chunks, as they are.
+pub fn png_file(chunks: &[Vec<u8>]) -> Vec<u8> {
+    [b"\x89PNG\r\n\x1a\n".as_slice(), &chunks.concat()].concat()
+}
+
+/// A 1x1 PNG, with meta chunks between its IHDR and IDAT.
+pub fn png(meta: &[Vec<u8>]) -> Vec<u8> {
+    png_file(&[
+        png_chunk(b"IHDR", &IHDR),
+        meta.concat(),
+        png_chunk(b"IDAT", IDAT),
+        png_chunk(b"IEND", &[]),
+    ])
+}
+
+/// A RIFF file of form, e.g. WEBP, with its chunks padded to an even size.
+pub fn riff_file(form: &[u8; 4], chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+    let mut body = form.to_vec();
+    for (id, data) in chunks {
+        body.extend_from_slice(*id);
+        body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        body.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            body.push(0);
+        }
+    }
+    let mut b = b"RIFF".to_vec();
+    b.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    b.extend_from_slice(&body);
+    b
+}
+
+/// An ISO BMFF box with a 32-bit size.
+pub fn bmff_box(typ: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut b = ((8 + data.len()) as u32).to_be_bytes().to_vec();
+    b.extend_from_slice(typ);
+    b.extend_from_slice(data);
+    b
+}
+
+/// An EBML element with a 1 byte size.
+pub fn ebml_element(id: &[u8], data: &[u8]) -> Vec<u8> {
+    [id, &[0x80 | data.len() as u8], data].concat()
+}
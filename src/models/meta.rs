@@ -0,0 +1,48 @@
+// Copyright (c) 2026, Anthony DeDominic <adedomin@gmail.com>
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// What we remember about an upload, stored as a JSON sidecar in the store's state directory.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UploadMeta {
+    /// Seconds since the UNIX epoch.
+    pub created: u64,
+    pub size: u64,
+    pub ext: String,
+    pub sha256: String,
+    /// Salted hash of the uploader's IP, only known when ratelimiting is enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip_hash: Option<String>,
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+impl UploadMeta {
+    pub fn new(size: u64, ext: &str, sha256: &[u8], ip_hash: Option<String>) -> Self {
+        Self {
+            created: unix_now(),
+            size,
+            ext: ext.to_owned(),
+            sha256: hex::encode(sha256),
+            ip_hash,
+        }
+    }
+}
@@ -11,8 +11,42 @@
 // WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
 // ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
 // OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
-pub const MIME: [(&[u8], usize, &str); 13] = [
-    (&[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A], 0, "png"),
+/// Extension and Content-Type of every format [`detect_ext`] knows.
+pub const MEDIA: [(&str, &str); 26] = [
+    ("png", "image/png"),
+    ("apng", "image/apng"),
+    ("jpg", "image/jpeg"),
+    ("jxl", "image/jxl"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("avif", "image/avif"),
+    ("heic", "image/heic"),
+    ("heif", "image/heif"),
+    ("bmp", "image/bmp"),
+    ("tiff", "image/tiff"),
+    ("ico", "image/x-icon"),
+    ("webm", "video/webm"),
+    ("mkv", "video/x-matroska"),
+    ("mp4", "video/mp4"),
+    ("m4v", "video/x-m4v"),
+    ("mov", "video/quicktime"),
+    ("3gp", "video/3gpp"),
+    ("3g2", "video/3gpp2"),
+    ("ogv", "video/ogg"),
+    ("m4a", "audio/mp4"),
+    ("ogg", "audio/ogg"),
+    ("opus", "audio/ogg"),
+    ("mp3", "audio/mpeg"),
+    ("flac", "audio/flac"),
+    ("wav", "audio/wav"),
+];
+
+/// How much of the start of a file [`detect_ext`] wants to see.
+/// Most formats need a few dozen bytes, but an APNG's acTL chunk may follow a color profile.
+pub const SNIFF_LEN: usize = 4096;
+
+/// Signatures at a fixed offset.
+const MAGIC: [(&[u8], usize, &str); 10] = [
     (&[0xFF, 0xD8, 0xFF], 0, "jpg"),
     (
         &[
@@ -21,47 +55,506 @@ pub const MIME: [(&[u8], usize, &str); 13] = [
         0,
         "jxl",
     ),
+    // a bare JPEG XL codestream.
+    (&[0xFF, 0x0A], 0, "jxl"),
     (b"GIF87a", 0, "gif"),
     (b"GIF89a", 0, "gif"),
-    (&[0x1A, 0x45, 0xDF, 0xA3], 0, "webm"),
-    (b"ftypMSNV", 4, "mp4"),
-    (b"ftypisom", 4, "mp4"),
-    (b"WEBP", 8, "webp"),
-    (b"ftypavif", 4, "avif"),
-    (b"ftypheic", 4, "heic"),
-    (b"ftypqt", 4, "mov"),
+    (b"II*\0", 0, "tiff"),
+    (b"MM\0*", 0, "tiff"),
+    // QuickTime from before ftyp boxes.
     (b"moov", 4, "mov"),
+    (b"fLaC", 0, "flac"),
+    (b"ID3", 0, "mp3"),
+];
+
+/// ISO-BMFF brands that name a format, see [`ftyp`].
+const BRANDS: [(&[u8; 4], &str); 26] = [
+    (b"avif", "avif"),
+    (b"avis", "avif"),
+    (b"heic", "heic"),
+    (b"heix", "heic"),
+    (b"heim", "heic"),
+    (b"heis", "heic"),
+    (b"hevc", "heic"),
+    (b"hevx", "heic"),
+    (b"M4V ", "m4v"),
+    (b"M4VH", "m4v"),
+    (b"M4VP", "m4v"),
+    (b"M4A ", "m4a"),
+    (b"M4B ", "m4a"),
+    (b"qt  ", "mov"),
+    (b"3gp4", "3gp"),
+    (b"3gp5", "3gp"),
+    (b"3gp6", "3gp"),
+    (b"3gp7", "3gp"),
+    (b"3ge6", "3gp"),
+    (b"3ge7", "3gp"),
+    (b"3gg6", "3gp"),
+    (b"3g2a", "3g2"),
+    (b"3g2b", "3g2"),
+    (b"3g2c", "3g2"),
+    (b"MSNV", "mp4"),
+    (b"F4V ", "mp4"),
+];
+
+/// Brands that only say which family a file is from, used when no brand in [`BRANDS`] is listed.
+const GENERIC_BRANDS: [(&[u8; 4], &str); 15] = [
+    (b"mif1", "heif"),
+    (b"msf1", "heif"),
+    (b"miaf", "heif"),
+    (b"isom", "mp4"),
+    (b"iso2", "mp4"),
+    (b"iso3", "mp4"),
+    (b"iso4", "mp4"),
+    (b"iso5", "mp4"),
+    (b"iso6", "mp4"),
+    (b"mp41", "mp4"),
+    (b"mp42", "mp4"),
+    (b"mp71", "mp4"),
+    (b"avc1", "mp4"),
+    (b"dash", "mp4"),
+    (b"mmp4", "mp4"),
 ];
 
-pub const BYTES_NEEDED: usize = {
-    let mut i = 0;
-    let mut max = usize::MIN;
-    while i < MIME.len() {
-        let (magic, off, _) = MIME[i];
-        let siz = magic.len() + off;
-        if max < siz {
-            max = siz;
+/// Matroska DocTypes, see [`ebml`].
+const DOCTYPES: [(&[u8], &str); 2] = [(b"webm", "webm"), (b"matroska", "mkv")];
+
+/// RIFF form types.
+const RIFF: [(&[u8], &str); 2] = [(b"WEBP", "webp"), (b"WAVE", "wav")];
+
+/// The start of the first packet in an Ogg stream, anything else is assumed to be audio.
+const OGG_CODECS: [(&[u8], &str); 2] = [(b"OpusHead", "opus"), (b"\x80theora", "ogv")];
+
+const PNG: &[u8] = &[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+
+#[cfg_attr(not(feature = "serve-files"), allow(unused))]
+pub fn content_type(ext: &str) -> &'static str {
+    match MEDIA.iter().find(|(e, _)| ext == *e) {
+        Some((_, t)) => t,
+        None if ext == "txt" => "text/plain; charset=utf-8",
+        None => "application/octet-stream",
+    }
+}
+
+fn be_u32(bytes: &[u8]) -> Option<usize> {
+    Some(u32::from_be_bytes(bytes.get(..4)?.try_into().ok()?) as usize)
+}
+
+/// A PNG, or an APNG if an animation control chunk comes before the image data.
+fn png(bytes: &[u8]) -> Option<&'static str> {
+    let mut chunks = bytes.strip_prefix(PNG)?;
+    while let Some(len) = be_u32(chunks) {
+        match chunks.get(4..8) {
+            Some(b"acTL") => return Some("apng"),
+            Some(b"IDAT") | None => break,
+            _ => (),
         }
-        i += 1;
+        // length, type, data and crc.
+        chunks = chunks.get(len.saturating_add(12)..).unwrap_or_default();
     }
-    max
-};
+    Some("png")
+}
 
-/// Attempt to detect an extention from arbitrary bytes.
-pub fn detect_ext(bytes: &[u8]) -> Option<&'static str> {
-    if bytes.len() < BYTES_NEEDED {
+/// An ISO-BMFF file (MP4, MOV, HEIF, AVIF, 3GP...) by the brands in its leading ftyp box.
+/// The major brand decides unless it is generic, e.g. mif1 only says HEIF, so the compatible
+/// brands may narrow it down to HEIC or AVIF.
+fn ftyp(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.get(4..8)? != b"ftyp" {
         return None;
     }
-    MIME.iter()
-        .find_map(|&(magic, off, ext)| bytes[off..BYTES_NEEDED].starts_with(magic).then_some(ext))
+    // 0 means the box runs to the end of the file.
+    let size = match be_u32(bytes)? {
+        0 => bytes.len(),
+        size => size.min(bytes.len()),
+    };
+    let body = bytes.get(8..size)?;
+    let major = body.get(..4)?;
+    // skip the minor version.
+    let compatible = body.get(8..).unwrap_or_default().chunks_exact(4);
+    let brands = std::iter::once(major).chain(compatible);
+    let find = |table: &[(&[u8; 4], &'static str)]| {
+        brands
+            .clone()
+            .find_map(|b| table.iter().find(|(brand, _)| *brand == b))
+            .map(|(_, ext)| *ext)
+    };
+    find(&BRANDS).or_else(|| find(&GENERIC_BRANDS))
 }
 
-// fn test_file<T: AsRef<std::path::Path>>(p: T) -> Option<&'static str> {
-//     let mut f = std::fs::File::open(p).ok()?;
-//     let mut buf = vec![0; 64];
-//     use std::io::Read;
-//     f.read_exact(&mut buf).ok()?;
-//     drop(f);
-//     println!("{buf:?}");
-//     get_ext(&buf)
-// }
+/// An EBML variable length integer and how many bytes it took.
+/// Element IDs keep their length marker, sizes don't.
+fn vint(bytes: &[u8], keep_marker: bool) -> Option<(u64, usize)> {
+    let first = *bytes.first()?;
+    let len = first.leading_zeros() as usize + 1;
+    if len > 8 {
+        return None;
+    }
+    let mut v = if keep_marker {
+        first as u64
+    } else {
+        first as u64 & (0xFF >> len)
+    };
+    for &b in bytes.get(1..len)? {
+        v = v << 8 | b as u64;
+    }
+    Some((v, len))
+}
+
+/// WebM or Matroska, by the DocType in the EBML header.
+fn ebml(bytes: &[u8]) -> Option<&'static str> {
+    let rest = bytes.strip_prefix(&[0x1A, 0x45, 0xDF, 0xA3])?;
+    let (size, n) = vint(rest, false)?;
+    let rest = &rest[n..];
+    let mut header = usize::try_from(size)
+        .ok()
+        .and_then(|size| rest.get(..size))
+        .unwrap_or(rest);
+    while !header.is_empty() {
+        let (id, n) = vint(header, true)?;
+        let (len, m) = vint(&header[n..], false)?;
+        let data = &header[n + m..];
+        let len = usize::try_from(len).ok()?;
+        if id == 0x4282 {
+            // strings may be padded with zeros.
+            let doctype = data.get(..len)?.split(|&c| c == 0).next()?;
+            return DOCTYPES
+                .iter()
+                .find(|(d, _)| *d == doctype)
+                .map(|(_, ext)| *ext);
+        }
+        header = data.get(len..)?;
+    }
+    None
+}
+
+fn riff(bytes: &[u8]) -> Option<&'static str> {
+    if !bytes.starts_with(b"RIFF") {
+        return None;
+    }
+    let form = bytes.get(8..12)?;
+    RIFF.iter().find(|(f, _)| *f == form).map(|(_, ext)| *ext)
+}
+
+fn ogg(bytes: &[u8]) -> Option<&'static str> {
+    if !bytes.starts_with(b"OggS") {
+        return None;
+    }
+    // a 27 byte page header and a segment table come before the first packet.
+    let segments = *bytes.get(26)? as usize;
+    let packet = bytes.get(27 + segments..)?;
+    Some(
+        OGG_CODECS
+            .iter()
+            .find(|(start, _)| packet.starts_with(start))
+            .map_or("ogg", |(_, ext)| ext),
+    )
+}
+
+/// BMP has a two letter signature, so also check for a known DIB header size.
+fn bmp(bytes: &[u8]) -> Option<&'static str> {
+    if !bytes.starts_with(b"BM") || bytes.get(6..10)? != [0; 4] {
+        return None;
+    }
+    let dib = u32::from_le_bytes(bytes.get(14..18)?.try_into().ok()?);
+    [12, 40, 52, 56, 64, 108, 124]
+        .contains(&dib)
+        .then_some("bmp")
+}
+
+fn ico(bytes: &[u8]) -> Option<&'static str> {
+    let count = u16::from_le_bytes(
+        bytes
+            .strip_prefix(&[0, 0, 1, 0])?
+            .get(..2)?
+            .try_into()
+            .ok()?,
+    );
+    // the first entry's reserved byte.
+    (count > 0 && *bytes.get(9)? == 0).then_some("ico")
+}
+
+/// MP3 without an ID3 tag starts with a frame header: 11 sync bits, then a valid MPEG
+/// version, layer III, and a bitrate and sample rate that aren't reserved.
+fn mp3(bytes: &[u8]) -> Option<&'static str> {
+    let &[0xFF, b1, b2, ..] = bytes else {
+        return None;
+    };
+    let (version, layer) = ((b1 >> 3) & 3, (b1 >> 1) & 3);
+    let (bitrate, rate) = (b2 >> 4, (b2 >> 2) & 3);
+    (b1 & 0xE0 == 0xE0 && version != 1 && layer == 1 && !matches!(bitrate, 0 | 15) && rate != 3)
+        .then_some("mp3")
+}
+
+/// Formats that take more than a fixed signature to tell apart, tried after [`MAGIC`].
+type Sniffer = fn(&[u8]) -> Option<&'static str>;
+const SNIFFERS: [Sniffer; 8] = [png, ftyp, ebml, riff, ogg, bmp, ico, mp3];
+
+/// Attempt to detect an extention from the start of a file, ideally [`SNIFF_LEN`] bytes of it.
+pub fn detect_ext(bytes: &[u8]) -> Option<&'static str> {
+    MAGIC
+        .iter()
+        .find(|(magic, off, _)| bytes.get(*off..).is_some_and(|b| b.starts_with(magic)))
+        .map(|(_, _, ext)| *ext)
+        .or_else(|| SNIFFERS.iter().find_map(|sniff| sniff(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::fixtures::{bmff_box, ebml_element, png_chunk};
+
+    /// Like [`detect_ext`], but every answer must have a Content-Type.
+    fn detect(bytes: &[u8]) -> Option<&'static str> {
+        let ext = detect_ext(bytes);
+        if let Some(ext) = ext {
+            assert_ne!(content_type(ext), "application/octet-stream", "{ext}");
+        }
+        ext
+    }
+
+    fn ftyp_box(major: &[u8; 4], compatible: &[&[u8; 4]]) -> Vec<u8> {
+        let mut brands = major.to_vec();
+        brands.extend_from_slice(&[0; 4]);
+        for brand in compatible {
+            brands.extend_from_slice(*brand);
+        }
+        // the next box.
+        [bmff_box(b"ftyp", &brands), bmff_box(b"free", &[])].concat()
+    }
+
+    fn ebml_header(doctype: &[u8]) -> Vec<u8> {
+        // EBMLVersion 1, then the DocType.
+        let elements = [
+            ebml_element(&[0x42, 0x86], &[0x01]),
+            ebml_element(&[0x42, 0x82], doctype),
+        ]
+        .concat();
+        let mut b = ebml_element(&[0x1A, 0x45, 0xDF, 0xA3], &elements);
+        // the Segment that follows.
+        b.extend_from_slice(&[0x18, 0x53, 0x80, 0x67]);
+        b
+    }
+
+    fn ogg_page(packet: &[u8]) -> Vec<u8> {
+        let mut b = b"OggS\0\x02".to_vec();
+        b.extend_from_slice(&[0; 20]);
+        b.push(1);
+        b.push(packet.len() as u8);
+        b.extend_from_slice(packet);
+        b
+    }
+
+    #[test]
+    fn media_table_is_unique() {
+        for (i, (ext, _)) in MEDIA.iter().enumerate() {
+            assert!(!MEDIA[..i].iter().any(|(e, _)| e == ext), "{ext}");
+        }
+        assert_eq!(content_type("txt"), "text/plain; charset=utf-8");
+        assert_eq!(content_type("exe"), "application/octet-stream");
+    }
+
+    #[test]
+    fn png_and_apng() {
+        let ihdr = png_chunk(b"IHDR", &[0; 13]);
+        let idat = png_chunk(b"IDAT", &[0; 8]);
+        let png = [PNG, &ihdr, &idat].concat();
+        assert_eq!(detect(&png), Some("png"));
+        // acTL may come after other chunks, like a color profile.
+        let actl = png_chunk(b"acTL", &[0, 0, 0, 2, 0, 0, 0, 0]);
+        let iccp = png_chunk(b"iCCP", &[0; 3000]);
+        let apng = [PNG, &ihdr, &iccp, &actl, &idat].concat();
+        assert_eq!(detect(&apng), Some("apng"));
+        // acTL after the image data doesn't count.
+        let late = [PNG, &ihdr, &idat, &actl].concat();
+        assert_eq!(detect(&late), Some("png"));
+        // not enough bytes to find out, but it is a PNG.
+        assert_eq!(detect(&[PNG, &ihdr[..10]].concat()), Some("png"));
+    }
+
+    #[test]
+    fn jpg() {
+        assert_eq!(detect(b"\xFF\xD8\xFF\xE0\0\x10JFIF\0"), Some("jpg"));
+        assert_eq!(detect(b"\xFF\xD8\xFF\xE1\0\x10Exif\0"), Some("jpg"));
+    }
+
+    #[test]
+    fn jxl() {
+        assert_eq!(
+            detect(b"\0\0\0\x0CJXL \x0D\x0A\x87\x0A\0\0\0\x14ftypjxl "),
+            Some("jxl")
+        );
+        assert_eq!(detect(b"\xFF\x0A\xFA\x7F"), Some("jxl"));
+    }
+
+    #[test]
+    fn gif() {
+        assert_eq!(detect(b"GIF87a\x01\0\x01\0"), Some("gif"));
+        assert_eq!(detect(b"GIF89a\x01\0\x01\0"), Some("gif"));
+    }
+
+    #[test]
+    fn webp_and_wav() {
+        assert_eq!(detect(b"RIFF\x24\0\0\0WEBPVP8 "), Some("webp"));
+        assert_eq!(detect(b"RIFF\x24\0\0\0WAVEfmt "), Some("wav"));
+        assert_eq!(detect(b"RIFF\x24\0\0\0AVI LIST"), None);
+        assert_eq!(detect(b"JUNK\x24\0\0\0WEBPVP8 "), None);
+    }
+
+    #[test]
+    fn mp4_brands() {
+        for major in [
+            b"isom", b"iso2", b"mp41", b"mp42", b"avc1", b"MSNV", b"dash",
+        ] {
+            assert_eq!(
+                detect(&ftyp_box(major, &[b"isom"])),
+                Some("mp4"),
+                "{major:?}"
+            );
+        }
+        assert_eq!(
+            detect(&ftyp_box(b"isom", &[b"isom", b"iso2", b"avc1", b"mp41"])),
+            Some("mp4")
+        );
+    }
+
+    #[test]
+    fn m4v_m4a_and_3gp() {
+        assert_eq!(detect(&ftyp_box(b"M4V ", &[b"M4V ", b"mp42"])), Some("m4v"));
+        // a generic major brand with a specific compatible one.
+        assert_eq!(detect(&ftyp_box(b"mp42", &[b"isom", b"M4V "])), Some("m4v"));
+        assert_eq!(detect(&ftyp_box(b"M4A ", &[b"M4A ", b"mp42"])), Some("m4a"));
+        assert_eq!(detect(&ftyp_box(b"3gp4", &[b"isom", b"3gp4"])), Some("3gp"));
+        assert_eq!(detect(&ftyp_box(b"3gp6", &[])), Some("3gp"));
+        assert_eq!(detect(&ftyp_box(b"3g2a", &[b"3g2a"])), Some("3g2"));
+    }
+
+    #[test]
+    fn mov() {
+        assert_eq!(detect(&ftyp_box(b"qt  ", &[b"qt  "])), Some("mov"));
+        assert_eq!(detect(b"\0\0\x01\0moov\0\0\0\x6cmvhd"), Some("mov"));
+    }
+
+    #[test]
+    fn heif_family() {
+        assert_eq!(
+            detect(&ftyp_box(b"heic", &[b"mif1", b"heic"])),
+            Some("heic")
+        );
+        assert_eq!(
+            detect(&ftyp_box(b"heix", &[b"mif1", b"heix"])),
+            Some("heic")
+        );
+        assert_eq!(
+            detect(&ftyp_box(b"hevc", &[b"msf1", b"hevc"])),
+            Some("heic")
+        );
+        assert_eq!(
+            detect(&ftyp_box(b"mif1", &[b"mif1", b"heic"])),
+            Some("heic")
+        );
+        assert_eq!(
+            detect(&ftyp_box(b"mif1", &[b"mif1", b"miaf"])),
+            Some("heif")
+        );
+        assert_eq!(detect(&ftyp_box(b"msf1", &[b"msf1"])), Some("heif"));
+    }
+
+    #[test]
+    fn avif() {
+        assert_eq!(
+            detect(&ftyp_box(b"avif", &[b"avif", b"mif1", b"miaf"])),
+            Some("avif")
+        );
+        assert_eq!(
+            detect(&ftyp_box(b"avis", &[b"avis", b"msf1", b"miaf"])),
+            Some("avif")
+        );
+        assert_eq!(
+            detect(&ftyp_box(b"mif1", &[b"avif", b"mif1", b"miaf"])),
+            Some("avif")
+        );
+    }
+
+    #[test]
+    fn unknown_or_broken_ftyp() {
+        // e.g. Canon raw.
+        assert_eq!(detect(&ftyp_box(b"crx ", &[b"crx ", b"isom"])), Some("mp4"));
+        assert_eq!(detect(&ftyp_box(b"crx ", &[b"crx "])), None);
+        assert_eq!(detect(b"\0\0\0\x18ftypis"), None);
+        // brands past the end of the box are from the next one.
+        let mut b = ftyp_box(b"mp42", &[]);
+        b.extend_from_slice(b"avif");
+        assert_eq!(detect(&b), Some("mp4"));
+    }
+
+    #[test]
+    fn webm_and_mkv() {
+        assert_eq!(detect(&ebml_header(b"webm")), Some("webm"));
+        assert_eq!(detect(&ebml_header(b"matroska")), Some("mkv"));
+        assert_eq!(detect(&ebml_header(b"webm\0\0")), Some("webm"));
+        assert_eq!(detect(&ebml_header(b"mka?")), None);
+        // sizes may use more bytes than they need.
+        let b = [
+            &[0x1A, 0x45, 0xDF, 0xA3, 0x40, 0x08, 0x42, 0x82, 0x40, 0x04][..],
+            b"webm",
+        ]
+        .concat();
+        assert_eq!(detect(&b), Some("webm"));
+        assert_eq!(detect(&[0x1A, 0x45, 0xDF, 0xA3, 0x84, 0x42]), None);
+        assert_eq!(detect(&[0x1A, 0x45, 0xDF, 0xA3, 0x01, 0, 0]), None);
+    }
+
+    #[test]
+    fn bmp() {
+        let mut b = b"BM\x36\0\x0C\0\0\0\0\0\x36\0\0\0".to_vec();
+        b.extend_from_slice(&40u32.to_le_bytes());
+        assert_eq!(detect(&b), Some("bmp"));
+        assert_eq!(detect(b"BMW and other words starting with BM"), None);
+    }
+
+    #[test]
+    fn tiff() {
+        assert_eq!(detect(b"II*\0\x08\0\0\0"), Some("tiff"));
+        assert_eq!(detect(b"MM\0*\0\0\0\x08"), Some("tiff"));
+    }
+
+    #[test]
+    fn ico() {
+        let b = b"\0\0\x01\0\x01\0\x10\x10\0\0\x01\0\x20\0";
+        assert_eq!(detect(b), Some("ico"));
+        assert_eq!(detect(b"\0\0\x01\0\0\0"), None);
+    }
+
+    #[test]
+    fn ogg() {
+        assert_eq!(detect(&ogg_page(b"OpusHead\x01\x02")), Some("opus"));
+        assert_eq!(detect(&ogg_page(b"\x01vorbis\0\0\0\0")), Some("ogg"));
+        assert_eq!(detect(&ogg_page(b"\x7FFLAC\x01\0")), Some("ogg"));
+        assert_eq!(detect(&ogg_page(b"\x80theora\x03\x02")), Some("ogv"));
+    }
+
+    #[test]
+    fn mp3() {
+        assert_eq!(detect(b"ID3\x04\0\0\0\0\x01\0"), Some("mp3"));
+        // MPEG-1 and MPEG-2 layer III frames.
+        assert_eq!(detect(b"\xFF\xFB\x90\x64"), Some("mp3"));
+        assert_eq!(detect(b"\xFF\xF3\x48\xC4"), Some("mp3"));
+        // AAC in ADTS, and a reserved bitrate.
+        assert_eq!(detect(b"\xFF\xF1\x50\x80"), None);
+        assert_eq!(detect(b"\xFF\xFB\xF0\x64"), None);
+    }
+
+    #[test]
+    fn flac() {
+        assert_eq!(detect(b"fLaC\0\0\0\x22"), Some("flac"));
+    }
+
+    #[test]
+    fn not_media() {
+        assert_eq!(detect(b""), None);
+        assert_eq!(detect(b"hello, world"), None);
+        assert_eq!(detect(b"<!DOCTYPE html>"), None);
+        assert_eq!(detect(b"PK\x03\x04"), None);
+        assert_eq!(detect(b"%PDF-1.7"), None);
+    }
+}
@@ -13,5 +13,10 @@
 // OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
 pub mod api;
 pub mod dropfs;
+#[cfg(test)]
+pub mod fixtures;
+pub mod meta;
 pub mod mime;
+pub mod strip;
+pub mod validate;
 pub mod webdata;
@@ -0,0 +1,805 @@
+// Copyright (c) 2026, Anthony DeDominic <adedomin@gmail.com>
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+use std::{
+    fs::File,
+    io::{self, ErrorKind, Read},
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+use super::validate::ValidateErr::{self, Malformed, Truncated};
+
+type Stripped = Result<Option<Vec<u8>>, ValidateErr>;
+/// An ISO BMFF box as its type, where its contents start and its contents.
+type IsoBox<'a> = ([u8; 4], usize, &'a [u8]);
+
+const EXIF: &[u8] = b"Exif\0\0";
+const XMP: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const XMP_EXTENSION: &[u8] = b"http://ns.adobe.com/xmp/extension/\0";
+const IPTC: &[u8] = b"Photoshop 3.0\0";
+/// Points at extra images after the end of a JPEG, which are dropped with it.
+const MPF: &[u8] = b"MPF\0";
+const ORIENTATION: u16 = 0x0112;
+/// Uploads are stripped in memory, so stores that strip metadata can't take larger ones.
+pub const MAX_STRIP_SIZ: usize = 67108864 /* 64MiB */;
+
+/// The orientation tag of an Exif TIFF structure, unless it is missing or the default.
+fn exif_orientation(tiff: &[u8]) -> Option<u16> {
+    let be = match tiff.get(..4)? {
+        b"MM\0*" => true,
+        b"II*\0" => false,
+        _ => return None,
+    };
+    let u16_at = |i: usize| {
+        let b = tiff.get(i..i + 2)?.try_into().ok()?;
+        Some(if be {
+            u16::from_be_bytes(b)
+        } else {
+            u16::from_le_bytes(b)
+        })
+    };
+    let u32_at = |i: usize| {
+        let b = tiff.get(i..i + 4)?.try_into().ok()?;
+        Some(if be {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        })
+    };
+    let ifd = u32_at(4)? as usize;
+    let count = u16_at(ifd)? as usize;
+    (0..count)
+        .find_map(|i| {
+            let entry = ifd + 2 + i * 12;
+            // a SHORT, which fits in the entry itself.
+            (u16_at(entry)? == ORIENTATION && u16_at(entry + 2)? == 3).then(|| u16_at(entry + 8))?
+        })
+        .filter(|o| (2..=8).contains(o))
+}
+
+/// An Exif TIFF structure with nothing but an orientation.
+fn orientation_tiff(orientation: Option<u16>) -> Vec<u8> {
+    let mut tiff = b"MM\0*\0\0\0\x08".to_vec();
+    match orientation {
+        Some(o) => {
+            tiff.extend_from_slice(&1u16.to_be_bytes());
+            tiff.extend_from_slice(&ORIENTATION.to_be_bytes());
+            // a SHORT, one of them.
+            tiff.extend_from_slice(&[0, 3, 0, 0, 0, 1]);
+            tiff.extend_from_slice(&o.to_be_bytes());
+            tiff.extend_from_slice(&[0, 0]);
+        }
+        None => tiff.extend_from_slice(&0u16.to_be_bytes()),
+    }
+    // no next IFD.
+    tiff.extend_from_slice(&[0; 4]);
+    tiff
+}
+
+fn be_u16(data: &[u8], at: usize) -> Result<usize, ValidateErr> {
+    let b = data.get(at..at + 2).ok_or(Truncated)?;
+    Ok(u16::from_be_bytes(b.try_into().unwrap()) as usize)
+}
+
+fn be_u32(data: &[u8], at: usize) -> Result<usize, ValidateErr> {
+    let b = data.get(at..at + 4).ok_or(Truncated)?;
+    Ok(u32::from_be_bytes(b.try_into().unwrap()) as usize)
+}
+
+fn le_u32(data: &[u8], at: usize) -> Result<usize, ValidateErr> {
+    let b = data.get(at..at + 4).ok_or(Truncated)?;
+    Ok(u32::from_le_bytes(b.try_into().unwrap()) as usize)
+}
+
+/// Drop Exif, XMP and IPTC segments, and anything after the end of the image.
+fn jpeg(data: &[u8]) -> Stripped {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return Err(Malformed("not a JPEG"));
+    }
+    let mut out = data[..2].to_vec();
+    let mut pos = 2;
+    let mut changed = false;
+    let mut kept_orientation = false;
+    loop {
+        if data.get(pos) != Some(&0xFF) {
+            return Err(Malformed("expected a JPEG marker"));
+        }
+        // fill bytes.
+        while data.get(pos + 1) == Some(&0xFF) {
+            pos += 1;
+        }
+        let marker = *data.get(pos + 1).ok_or(Truncated)?;
+        match marker {
+            // EOI
+            0xD9 => {
+                out.extend_from_slice(&[0xFF, 0xD9]);
+                changed |= pos + 2 < data.len();
+                break;
+            }
+            // markers without a length.
+            0x01 | 0xD0..=0xD7 => {
+                out.extend_from_slice(&[0xFF, marker]);
+                pos += 2;
+                continue;
+            }
+            0x00 | 0xD8 => return Err(Malformed("unexpected JPEG marker")),
+            _ => (),
+        }
+        let len = be_u16(data, pos + 2)?;
+        if len < 2 {
+            return Err(Malformed("bad JPEG segment length"));
+        }
+        let end = pos + 2 + len;
+        let segment = data.get(pos..end).ok_or(Truncated)?;
+        let payload = &segment[4..];
+        let app = |n: u8, sig: &[u8]| marker == n && payload.starts_with(sig);
+        if app(0xE1, EXIF) {
+            if !kept_orientation && let Some(o) = exif_orientation(&payload[EXIF.len()..]) {
+                let tiff = orientation_tiff(Some(o));
+                out.extend_from_slice(&[0xFF, 0xE1]);
+                out.extend_from_slice(&((2 + EXIF.len() + tiff.len()) as u16).to_be_bytes());
+                out.extend_from_slice(EXIF);
+                out.extend_from_slice(&tiff);
+                kept_orientation = true;
+            }
+            changed = true;
+        } else if app(0xE1, XMP) || app(0xE1, XMP_EXTENSION) || app(0xED, IPTC) || app(0xE2, MPF) {
+            changed = true;
+        } else {
+            out.extend_from_slice(segment);
+        }
+        pos = end;
+        // SOS: copy the compressed data up to the next marker.
+        if marker == 0xDA {
+            loop {
+                let ff = data[pos..]
+                    .iter()
+                    .position(|&b| b == 0xFF)
+                    .ok_or(Truncated)?;
+                let mut next = pos + ff + 1;
+                while data.get(next) == Some(&0xFF) {
+                    next += 1;
+                }
+                let m = *data.get(next).ok_or(Truncated)?;
+                if matches!(m, 0x00 | 0xD0..=0xD7) {
+                    out.extend_from_slice(&data[pos..=next]);
+                    pos = next + 1;
+                } else {
+                    out.extend_from_slice(&data[pos..pos + ff]);
+                    pos = next - 1;
+                    break;
+                }
+            }
+        }
+    }
+    Ok(changed.then_some(out))
+}
+
+fn png_chunk(out: &mut Vec<u8>, typ: &[u8; 4], data: &[u8]) {
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(typ);
+    crc.update(data);
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(typ);
+    out.extend_from_slice(data);
+    out.extend_from_slice(&crc.finalize().to_be_bytes());
+}
+
+/// Drop eXIf and text chunks, XMP is kept in iTXt.
+fn png(data: &[u8]) -> Stripped {
+    const SIGNATURE: &[u8] = &[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+    if !data.starts_with(SIGNATURE) {
+        return Err(Malformed("not a PNG"));
+    }
+    let mut out = SIGNATURE.to_vec();
+    let mut pos = SIGNATURE.len();
+    let mut changed = false;
+    let mut kept_orientation = false;
+    while pos < data.len() {
+        let end = pos + 12 + be_u32(data, pos)?;
+        let chunk = data.get(pos..end).ok_or(Truncated)?;
+        let typ = &chunk[4..8];
+        match typ {
+            b"eXIf" => {
+                if !kept_orientation && let Some(o) = exif_orientation(&chunk[8..chunk.len() - 4]) {
+                    png_chunk(&mut out, b"eXIf", &orientation_tiff(Some(o)));
+                    kept_orientation = true;
+                }
+                changed = true;
+            }
+            b"tEXt" | b"zTXt" | b"iTXt" => changed = true,
+            _ => out.extend_from_slice(chunk),
+        }
+        pos = end;
+        if typ == b"IEND" {
+            changed |= pos < data.len();
+            break;
+        }
+    }
+    Ok(changed.then_some(out))
+}
+
+/// Drop the EXIF and XMP chunks and their flags.
+fn webp(data: &[u8]) -> Stripped {
+    if data.get(..4) != Some(b"RIFF") || data.get(8..12) != Some(b"WEBP") {
+        return Err(Malformed("not a WebP"));
+    }
+    let end = (8 + le_u32(data, 4)?).min(data.len());
+    let mut out = data[..12].to_vec();
+    let mut pos = 12;
+    let mut changed = end < data.len();
+    let mut vp8x = None;
+    let mut kept_exif = false;
+    while pos + 8 <= end {
+        let len = le_u32(data, pos + 4)?;
+        let next = (pos + 8 + len + (len & 1)).min(end);
+        let chunk = data.get(pos..next).ok_or(Truncated)?;
+        match &chunk[..4] {
+            b"EXIF" => {
+                let exif = chunk.get(8..8 + len).ok_or(Truncated)?;
+                let tiff = exif.strip_prefix(EXIF).unwrap_or(exif);
+                if !kept_exif && let Some(o) = exif_orientation(tiff) {
+                    let tiff = orientation_tiff(Some(o));
+                    out.extend_from_slice(b"EXIF");
+                    out.extend_from_slice(&(tiff.len() as u32).to_le_bytes());
+                    out.extend_from_slice(&tiff);
+                    kept_exif = true;
+                }
+                changed = true;
+            }
+            b"XMP " => changed = true,
+            typ => {
+                if typ == b"VP8X" {
+                    vp8x = Some(out.len());
+                }
+                out.extend_from_slice(chunk);
+            }
+        }
+        pos = next;
+    }
+    if !changed {
+        return Ok(None);
+    }
+    if let Some(at) = vp8x {
+        let flags = out.get_mut(at + 8).ok_or(Truncated)?;
+        // XMP and EXIF
+        *flags &= !0x0C;
+        if kept_exif {
+            *flags |= 0x08;
+        }
+    }
+    let size = (out.len() - 8) as u32;
+    out[4..8].copy_from_slice(&size.to_le_bytes());
+    Ok(Some(out))
+}
+
+/// The boxes in data, offset by base.
+fn boxes(data: &[u8], base: usize) -> Result<Vec<IsoBox<'_>>, ValidateErr> {
+    let mut boxes = vec![];
+    let mut pos = 0;
+    while pos + 8 <= data.len() {
+        let typ = data[pos + 4..pos + 8].try_into().unwrap();
+        let (size, header) = match be_u32(data, pos)? {
+            0 => (data.len() - pos, 8),
+            1 => {
+                let b = data.get(pos + 8..pos + 16).ok_or(Truncated)?;
+                let size = u64::from_be_bytes(b.try_into().unwrap());
+                (usize::try_from(size).map_err(|_| Truncated)?, 16)
+            }
+            size => (size, 8),
+        };
+        if size < header {
+            return Err(Malformed("bad box size"));
+        }
+        // a 64-bit size can run past the end of memory, not just the data.
+        let end = pos.checked_add(size).ok_or(Truncated)?;
+        let contents = data.get(pos + header..end).ok_or(Truncated)?;
+        boxes.push((typ, base + pos + header, contents));
+        pos = end;
+    }
+    Ok(boxes)
+}
+
+/// Reads the fields of a box.
+struct Fields<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Fields<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], ValidateErr> {
+        let taken = self.data.get(self.pos..self.pos + n).ok_or(Truncated)?;
+        self.pos += n;
+        Ok(taken)
+    }
+
+    /// A big endian number n bytes long, 0 bytes is 0.
+    fn uint(&mut self, n: usize) -> Result<u64, ValidateErr> {
+        if n > 8 {
+            return Err(Malformed("bad field size"));
+        }
+        Ok(self.take(n)?.iter().fold(0, |v, &b| v << 8 | b as u64))
+    }
+
+    fn cstr(&mut self) -> Result<&'a [u8], ValidateErr> {
+        let rest = &self.data[self.pos..];
+        let len = rest.iter().position(|&b| b == 0).ok_or(Truncated)?;
+        self.pos += len + 1;
+        Ok(&rest[..len])
+    }
+}
+
+enum HeifMeta {
+    Exif,
+    Xmp,
+}
+
+const EMPTY_XMP_START: &[u8] =
+    br#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?><x:xmpmeta xmlns:x="adobe:ns:meta/"/>"#;
+const EMPTY_XMP_END: &[u8] = br#"<?xpacket end="w"?>"#;
+
+/// Blank the Exif and XMP items of a HEIF or AVIF image in place, so no offsets change.
+/// The Exif keeps its orientation, though HEIF readers go by the irot and imir properties.
+fn heif(data: &[u8]) -> Stripped {
+    let top = boxes(data, 0)?;
+    let Some(&(_, meta_at, meta)) = top.iter().find(|(typ, _, _)| typ == b"meta") else {
+        return Ok(None);
+    };
+    // a full box: version and flags first.
+    let children = boxes(meta.get(4..).ok_or(Truncated)?, meta_at + 4)?;
+    let mut items = vec![];
+    let mut locations = vec![];
+    let mut idat = None;
+    for (typ, at, contents) in children {
+        let mut f = Fields {
+            data: contents,
+            pos: 0,
+        };
+        match &typ {
+            b"iinf" => {
+                let version = f.uint(1)?;
+                f.take(3)?;
+                f.uint(if version == 0 { 2 } else { 4 })?;
+                for (typ, _, infe) in boxes(&contents[f.pos..], 0)? {
+                    let mut f = Fields { data: infe, pos: 0 };
+                    let version = f.uint(1)?;
+                    // older entries don't have an item type.
+                    if typ != *b"infe" || version < 2 {
+                        continue;
+                    }
+                    f.take(3)?;
+                    let id = f.uint(if version == 2 { 2 } else { 4 })?;
+                    f.take(2)?;
+                    let typ = f.take(4)?;
+                    // the item name comes before its content type.
+                    f.cstr()?;
+                    match typ {
+                        b"Exif" => items.push((id, HeifMeta::Exif)),
+                        b"mime" if f.cstr()? == b"application/rdf+xml" => {
+                            items.push((id, HeifMeta::Xmp))
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            b"iloc" => {
+                let version = f.uint(1)?;
+                f.take(3)?;
+                let sizes = f.take(2)?;
+                let (offset_size, length_size) =
+                    ((sizes[0] >> 4) as usize, (sizes[0] & 15) as usize);
+                let base_size = (sizes[1] >> 4) as usize;
+                let index_size = if matches!(version, 1 | 2) {
+                    (sizes[1] & 15) as usize
+                } else {
+                    0
+                };
+                let wide = if version < 2 { 2 } else { 4 };
+                for _ in 0..f.uint(wide)? {
+                    let id = f.uint(wide)?;
+                    let method = if matches!(version, 1 | 2) {
+                        f.uint(2)? & 15
+                    } else {
+                        0
+                    };
+                    f.uint(2)?;
+                    let base = f.uint(base_size)?;
+                    let mut extents = vec![];
+                    for _ in 0..f.uint(2)? {
+                        f.uint(index_size)?;
+                        let offset = base
+                            .checked_add(f.uint(offset_size)?)
+                            .ok_or(Malformed("bad item offset"))?;
+                        extents.push((offset, f.uint(length_size)?));
+                    }
+                    locations.push((id, method, extents));
+                }
+            }
+            b"idat" => idat = Some(at),
+            _ => (),
+        }
+    }
+
+    let mut out = data.to_vec();
+    let mut changed = false;
+    for (id, kind) in items {
+        let Some((_, method, extents)) = locations.iter().find(|(i, _, _)| *i == id) else {
+            continue;
+        };
+        // in the file, or in idat. Items made of other items are left alone.
+        let start = match method {
+            0 => 0,
+            1 => idat.ok_or(Malformed("no idat box"))?,
+            _ => continue,
+        };
+        let mut ranges = vec![];
+        for &(offset, len) in extents {
+            let from = usize::try_from(offset)
+                .ok()
+                .and_then(|o| o.checked_add(start))
+                .ok_or(Truncated)?;
+            let to = usize::try_from(len)
+                .ok()
+                .and_then(|l| l.checked_add(from))
+                .filter(|&to| to <= out.len())
+                .ok_or(Truncated)?;
+            ranges.push(from..to);
+        }
+        let old: Vec<u8> = ranges
+            .iter()
+            .flat_map(|r| &data[r.clone()])
+            .copied()
+            .collect();
+        let blank = match kind {
+            HeifMeta::Exif => {
+                // the TIFF structure follows an offset to it.
+                let skip = be_u32(&old, 0).unwrap_or(usize::MAX);
+                let orientation = old
+                    .get(4 + skip.min(old.len())..)
+                    .and_then(exif_orientation);
+                let mut exif = vec![0; 4];
+                exif.extend_from_slice(&orientation_tiff(orientation));
+                if exif.len() > old.len() {
+                    exif.clear();
+                }
+                exif.resize(old.len(), 0);
+                exif
+            }
+            HeifMeta::Xmp => {
+                let wrap = EMPTY_XMP_START.len() + EMPTY_XMP_END.len();
+                let mut xmp = vec![];
+                if wrap <= old.len() {
+                    xmp.extend_from_slice(EMPTY_XMP_START);
+                    xmp.resize(old.len() - EMPTY_XMP_END.len(), b' ');
+                    xmp.extend_from_slice(EMPTY_XMP_END);
+                }
+                xmp.resize(old.len(), b' ');
+                xmp
+            }
+        };
+        let mut blank = blank.as_slice();
+        for r in ranges {
+            let (now, rest) = blank.split_at(r.len());
+            out[r].copy_from_slice(now);
+            blank = rest;
+        }
+        changed = true;
+    }
+    Ok(changed.then_some(out))
+}
+
+/// Remove Exif, XMP and IPTC metadata, e.g. GPS coordinates and camera serial numbers,
+/// without decoding the image. Only the Exif orientation is kept. Returns None if there
+/// was nothing to remove or the format isn't handled.
+pub fn strip(ext: &str, data: &[u8]) -> Stripped {
+    match ext {
+        "jpg" => jpeg(data),
+        "png" | "apng" => png(data),
+        "webp" => webp(data),
+        "heic" | "heif" | "avif" => heif(data),
+        _ => Ok(None),
+    }
+}
+
+/// [`strip`] a staged upload in place, returns its new size and SHA-256 if it changed.
+pub async fn strip_file(ext: &str, path: &Path) -> Result<Option<(u64, [u8; 32])>, ValidateErr> {
+    let (ext, path): (String, PathBuf) = (ext.to_owned(), path.to_owned());
+    tokio::task::spawn_blocking(move || {
+        let mut data = vec![];
+        File::open(&path)?
+            .take(MAX_STRIP_SIZ as u64 + 1)
+            .read_to_end(&mut data)?;
+        // siz is checked against this when the config is loaded.
+        if data.len() > MAX_STRIP_SIZ {
+            return Err(ValidateErr::Io(io::Error::new(
+                ErrorKind::FileTooLarge,
+                "too large to strip metadata from",
+            )));
+        }
+        let Some(stripped) = strip(&ext, &data)? else {
+            return Ok(None);
+        };
+        std::fs::write(&path, &stripped)?;
+        Ok(Some((
+            stripped.len() as u64,
+            Sha256::digest(&stripped).into(),
+        )))
+    })
+    .await
+    .map_err(|e| ValidateErr::Io(std::io::Error::other(e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::fixtures::{self, ZIP, bmff_box, riff_file};
+
+    const SECRET: &[u8] = b"SECRET";
+
+    fn leaks(data: &[u8]) -> bool {
+        data.windows(SECRET.len()).any(|w| w == SECRET)
+    }
+
+    /// An Exif TIFF structure with an orientation and a GPS IFD.
+    fn exif_tiff(orientation: u16) -> Vec<u8> {
+        let mut tiff = b"MM\0*\0\0\0\x08\0\x02".to_vec();
+        tiff.extend_from_slice(&[0x01, 0x12, 0, 3, 0, 0, 0, 1]);
+        tiff.extend_from_slice(&orientation.to_be_bytes());
+        tiff.extend_from_slice(&[0, 0]);
+        tiff.extend_from_slice(&[0x88, 0x25, 0, 4, 0, 0, 0, 1, 0, 0, 0, 38]);
+        tiff.extend_from_slice(&[0; 4]);
+        // GPSProcessingMethod, pointing past the IFD.
+        tiff.extend_from_slice(&[0, 1, 0, 0x1B, 0, 7, 0, 0, 0, 6, 0, 0, 0, 56]);
+        tiff.extend_from_slice(&[0; 4]);
+        tiff.extend_from_slice(SECRET);
+        tiff
+    }
+
+    #[test]
+    fn orientation() {
+        assert_eq!(exif_orientation(&exif_tiff(6)), Some(6));
+        assert_eq!(exif_orientation(&exif_tiff(1)), None);
+        assert_eq!(exif_orientation(&exif_tiff(9)), None);
+        let le = b"II*\0\x08\0\0\0\x01\0\x12\x01\x03\0\x01\0\0\0\x08\0\0\0\0\0\0\0";
+        assert_eq!(exif_orientation(le), Some(8));
+        assert_eq!(exif_orientation(&orientation_tiff(Some(3))), Some(3));
+        assert_eq!(exif_orientation(b"MM\0*\xFF\xFF\xFF\xFF"), None);
+    }
+
+    fn segment(marker: u8, payload: &[&[u8]]) -> Vec<u8> {
+        let payload = payload.concat();
+        let mut seg = vec![0xFF, marker];
+        seg.extend_from_slice(&(payload.len() as u16 + 2).to_be_bytes());
+        seg.extend_from_slice(&payload);
+        seg
+    }
+
+    fn jpeg_file(meta: &[Vec<u8>]) -> Vec<u8> {
+        [
+            vec![0xFF, 0xD8],
+            segment(0xE0, &[b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0"]),
+            meta.concat(),
+            vec![0xFF, 0xDA, 0, 8, 1, 1, 0, 0, 0x3F, 0],
+            vec![0x12, 0xFF, 0x00, 0x34, 0xFF, 0xD0, 0x56],
+            vec![0xFF, 0xD9],
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn jpeg() {
+        let plain = jpeg_file(&[]);
+        assert_eq!(strip("jpg", &plain).unwrap(), None);
+
+        let tagged = jpeg_file(&[
+            segment(0xE1, &[EXIF, &exif_tiff(6)]),
+            segment(0xE1, &[XMP, b"<x:xmpmeta>", SECRET, b"</x:xmpmeta>"]),
+            segment(0xED, &[IPTC, SECRET]),
+        ]);
+        let out = strip("jpg", &[&tagged, ZIP].concat()).unwrap().unwrap();
+        assert!(!leaks(&out));
+        let upright = segment(0xE1, &[EXIF, &orientation_tiff(Some(6))]);
+        assert_eq!(out, jpeg_file(&[upright]));
+
+        let default = jpeg_file(&[segment(0xE1, &[EXIF, &exif_tiff(1)])]);
+        assert_eq!(strip("jpg", &default).unwrap(), Some(plain.clone()));
+        // the extra images of a Motion Photo.
+        let motion = [plain.as_slice(), &plain].concat();
+        assert_eq!(strip("jpg", &motion).unwrap(), Some(plain.clone()));
+        assert!(matches!(strip("jpg", &plain[..10]), Err(Truncated)));
+    }
+
+    fn png_chunks(data: &[u8]) -> Vec<(&[u8], &[u8])> {
+        let mut chunks = vec![];
+        let mut pos = 8;
+        while pos < data.len() {
+            let len = be_u32(data, pos).unwrap();
+            let (typ, rest) = data[pos + 4..pos + 12 + len].split_at(4);
+            let (contents, crc) = rest.split_at(len);
+            let mut hasher = crc32fast::Hasher::new();
+            hasher.update(typ);
+            hasher.update(contents);
+            assert_eq!(crc, hasher.finalize().to_be_bytes());
+            chunks.push((typ, contents));
+            pos += 12 + len;
+        }
+        chunks
+    }
+
+    fn png_file(meta: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let meta: Vec<_> = meta
+            .iter()
+            .map(|(typ, data)| fixtures::png_chunk(typ, data))
+            .collect();
+        fixtures::png(&meta)
+    }
+
+    #[test]
+    fn png() {
+        let plain = png_file(&[]);
+        assert_eq!(strip("png", &plain).unwrap(), None);
+
+        let xmp = [b"XML:com.adobe.xmp\0\0\0\0\0".as_slice(), SECRET].concat();
+        let tagged = png_file(&[
+            (b"tEXt", &[b"Comment\0".as_slice(), SECRET].concat()),
+            (b"eXIf", &exif_tiff(6)),
+            (b"iTXt", &xmp),
+        ]);
+        let out = strip("png", &[&tagged, ZIP].concat()).unwrap().unwrap();
+        assert!(!leaks(&out));
+        let types: Vec<_> = png_chunks(&out).into_iter().map(|(t, _)| t).collect();
+        assert_eq!(types, [b"IHDR", b"eXIf", b"IDAT", b"IEND"]);
+        assert_eq!(out, png_file(&[(b"eXIf", &orientation_tiff(Some(6)))]));
+
+        let default = png_file(&[(b"eXIf", &exif_tiff(1))]);
+        assert_eq!(strip("apng", &default).unwrap(), Some(plain));
+    }
+
+    fn vp8x(flags: u8) -> [u8; 10] {
+        [flags, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+    }
+
+    #[test]
+    fn webp() {
+        const VP8L: &[u8] = &[0x2F, 0, 0, 0, 0];
+        // alpha
+        let plain = riff_file(b"WEBP", &[(b"VP8X", &vp8x(0x10)), (b"VP8L", VP8L)]);
+        assert_eq!(strip("webp", &plain).unwrap(), None);
+
+        let tagged = riff_file(
+            b"WEBP",
+            &[
+                (b"VP8X", &vp8x(0x1C)),
+                (b"VP8L", VP8L),
+                (b"EXIF", &exif_tiff(6)),
+                (b"XMP ", &[b"<x>".as_slice(), SECRET, b"</x>"].concat()),
+            ],
+        );
+        let out = strip("webp", &[&tagged, ZIP].concat()).unwrap().unwrap();
+        assert!(!leaks(&out));
+        let upright = riff_file(
+            b"WEBP",
+            &[
+                (b"VP8X", &vp8x(0x18)),
+                (b"VP8L", VP8L),
+                (b"EXIF", &orientation_tiff(Some(6))),
+            ],
+        );
+        assert_eq!(out, upright);
+
+        // some writers keep the Exif header.
+        let prefixed = riff_file(
+            b"WEBP",
+            &[
+                (b"VP8X", &vp8x(0x18)),
+                (b"VP8L", VP8L),
+                (b"EXIF", &[EXIF, &exif_tiff(1)].concat()),
+            ],
+        );
+        assert_eq!(strip("webp", &prefixed).unwrap(), Some(plain));
+    }
+
+    /// A version 2 item info entry.
+    fn infe(id: u16, typ: &[u8; 4], mime: &[u8]) -> Vec<u8> {
+        let id = id.to_be_bytes();
+        bmff_box(
+            b"infe",
+            &[[2, 0, 0, 0].as_slice(), &id, &[0, 0], typ, b"\0", mime].concat(),
+        )
+    }
+
+    /// A HEIF image with an item for each of contents, which follow in mdat.
+    fn heif_file(items: &[(&[u8; 4], &[u8], &[u8])]) -> Vec<u8> {
+        let ftyp = bmff_box(b"ftyp", b"heic\0\0\0\0mif1heic");
+        let meta = |mdat_at: usize| {
+            let mut iinf = vec![0, 0, 0, 0];
+            iinf.extend_from_slice(&(items.len() as u16).to_be_bytes());
+            // version 0, 4 byte offsets and lengths without a base.
+            let mut iloc = vec![0, 0, 0, 0, 0x44, 0];
+            iloc.extend_from_slice(&(items.len() as u16).to_be_bytes());
+            let mut at = mdat_at + 8;
+            for (id, &(typ, mime, contents)) in (1u16..).zip(items) {
+                iinf.extend_from_slice(&infe(id, typ, mime));
+                iloc.extend_from_slice(&id.to_be_bytes());
+                iloc.extend_from_slice(&[0, 0, 0, 1]);
+                iloc.extend_from_slice(&(at as u32).to_be_bytes());
+                iloc.extend_from_slice(&(contents.len() as u32).to_be_bytes());
+                at += contents.len();
+            }
+            let hdlr = bmff_box(b"hdlr", &[&[0; 8], b"pict".as_slice(), &[0; 13]].concat());
+            let children = [hdlr, bmff_box(b"iinf", &iinf), bmff_box(b"iloc", &iloc)];
+            bmff_box(
+                b"meta",
+                &[&[0, 0, 0, 0], children.concat().as_slice()].concat(),
+            )
+        };
+        let mdat_at = ftyp.len() + meta(0).len();
+        let contents: Vec<_> = items.iter().map(|(_, _, c)| *c).collect();
+        [ftyp, meta(mdat_at), bmff_box(b"mdat", &contents.concat())].concat()
+    }
+
+    #[test]
+    fn heif() {
+        const IMAGE: &[u8] = b"\0\0\0\x10hevc image data";
+        let plain = heif_file(&[(b"hvc1", b"", IMAGE)]);
+        assert_eq!(strip("heic", &plain).unwrap(), None);
+
+        let exif = [b"\0\0\0\x06".as_slice(), EXIF, &exif_tiff(6)].concat();
+        let xmp = [
+            b"<x:xmpmeta>".as_slice(),
+            SECRET,
+            &[b' '; 128],
+            b"</x:xmpmeta>",
+        ]
+        .concat();
+        let tagged = heif_file(&[
+            (b"hvc1", b"", IMAGE),
+            (b"Exif", b"", &exif),
+            (b"mime", b"application/rdf+xml\0", &xmp),
+        ]);
+        let out = strip("avif", &tagged).unwrap().unwrap();
+        assert!(!leaks(&out));
+        // blanked in place, so every offset still points at the same thing.
+        assert_eq!(out.len(), tagged.len());
+        let exif_at = tagged.len() - xmp.len() - exif.len();
+        let image_at = exif_at - IMAGE.len();
+        assert_eq!(out[..exif_at], tagged[..exif_at]);
+        assert_eq!(&out[image_at..exif_at], IMAGE);
+        assert_eq!(out[exif_at..exif_at + 4], [0; 4]);
+        assert_eq!(exif_orientation(&out[exif_at + 4..]), Some(6));
+        let blank_xmp = &out[exif_at + exif.len()..];
+        assert!(blank_xmp.starts_with(EMPTY_XMP_START) && blank_xmp.ends_with(EMPTY_XMP_END));
+    }
+
+    #[test]
+    fn heif_box_sizes() {
+        let mut huge = bmff_box(b"ftyp", b"heic");
+        huge.extend_from_slice(&1u32.to_be_bytes());
+        huge.extend_from_slice(b"meta");
+        huge.extend_from_slice(&u64::MAX.to_be_bytes());
+        assert!(matches!(strip("heic", &huge), Err(Truncated)));
+        let mut short = bmff_box(b"ftyp", b"heic");
+        short.extend_from_slice(&[0, 0, 0, 4, b'm', b'e', b't', b'a']);
+        assert!(matches!(strip("heif", &short), Err(Malformed(_))));
+        // nothing to blank without a meta box.
+        let movie = [bmff_box(b"ftyp", b"heic"), bmff_box(b"mdat", b"data")].concat();
+        assert_eq!(strip("heic", &movie).unwrap(), None);
+    }
+
+    #[test]
+    fn other_formats() {
+        assert_eq!(strip("gif", b"GIF89a SECRET").unwrap(), None);
+    }
+}
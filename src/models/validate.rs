@@ -0,0 +1,811 @@
+// Copyright (c) 2026, Anthony DeDominic <adedomin@gmail.com>
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, ErrorKind, Read},
+    path::{Path, PathBuf},
+};
+
+/// Why an upload doesn't hold up, see [`validate`].
+#[derive(Debug, thiserror::Error)]
+pub enum ValidateErr {
+    #[error("Malformed file: {0}.")]
+    Malformed(&'static str),
+    #[error("The file is truncated.")]
+    Truncated,
+    #[error("{0} unexpected bytes after the end of the file.")]
+    Trailing(u64),
+    #[error("{0} files can't be checked.")]
+    Unchecked(String),
+    #[error(transparent)]
+    Io(io::Error),
+}
+
+impl From<io::Error> for ValidateErr {
+    fn from(e: io::Error) -> Self {
+        if e.kind() == ErrorKind::UnexpectedEof {
+            Self::Truncated
+        } else {
+            Self::Io(e)
+        }
+    }
+}
+
+use ValidateErr::{Malformed, Truncated};
+
+/// A file read front to back, knowing where it ends.
+struct Reader {
+    r: BufReader<File>,
+    pos: u64,
+    len: u64,
+}
+
+impl Reader {
+    fn remaining(&self) -> u64 {
+        self.len - self.pos
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos == self.len
+    }
+
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], ValidateErr> {
+        let mut buf = [0; N];
+        self.r.read_exact(&mut buf)?;
+        self.pos += N as u64;
+        Ok(buf)
+    }
+
+    fn byte(&mut self) -> Result<u8, ValidateErr> {
+        Ok(self.bytes::<1>()?[0])
+    }
+
+    fn skip(&mut self, n: u64) -> Result<(), ValidateErr> {
+        if n > self.remaining() {
+            return Err(Truncated);
+        }
+        // n is at most the file size, which fits.
+        self.r.seek_relative(n as i64)?;
+        self.pos += n;
+        Ok(())
+    }
+
+    /// Hand the next n bytes to f, e.g. for a checksum.
+    fn read_with(&mut self, n: u64, mut f: impl FnMut(&[u8])) -> Result<(), ValidateErr> {
+        if n > self.remaining() {
+            return Err(Truncated);
+        }
+        let mut left = n;
+        while left > 0 {
+            let buf = self.r.fill_buf()?;
+            if buf.is_empty() {
+                return Err(Truncated);
+            }
+            let take = buf.len().min(left.try_into().unwrap_or(usize::MAX));
+            f(&buf[..take]);
+            self.r.consume(take);
+            left -= take as u64;
+        }
+        self.pos += n;
+        Ok(())
+    }
+
+    /// Skip to just past the next 0xFF byte.
+    fn skip_past_ff(&mut self) -> Result<(), ValidateErr> {
+        loop {
+            let buf = self.r.fill_buf()?;
+            if buf.is_empty() {
+                return Err(Truncated);
+            }
+            let (n, found) = match buf.iter().position(|&b| b == 0xFF) {
+                Some(i) => (i + 1, true),
+                None => (buf.len(), false),
+            };
+            self.r.consume(n);
+            self.pos += n as u64;
+            if found {
+                return Ok(());
+            }
+        }
+    }
+
+    fn finish(&self) -> Result<(), ValidateErr> {
+        match self.remaining() {
+            0 => Ok(()),
+            n => Err(ValidateErr::Trailing(n)),
+        }
+    }
+}
+
+fn be_u16(r: &mut Reader) -> Result<u16, ValidateErr> {
+    Ok(u16::from_be_bytes(r.bytes()?))
+}
+
+fn be_u32(r: &mut Reader) -> Result<u32, ValidateErr> {
+    Ok(u32::from_be_bytes(r.bytes()?))
+}
+
+fn le_u32(r: &mut Reader) -> Result<u32, ValidateErr> {
+    Ok(u32::from_le_bytes(r.bytes()?))
+}
+
+/// Chunks from IHDR to IEND with valid checksums.
+fn png(r: &mut Reader) -> Result<(), ValidateErr> {
+    if r.bytes()? != [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A] {
+        return Err(Malformed("not a PNG"));
+    }
+    let mut first = true;
+    let mut has_data = false;
+    loop {
+        let len = be_u32(r)?;
+        if len > 0x7FFF_FFFF {
+            return Err(Malformed("PNG chunk too long"));
+        }
+        let typ: [u8; 4] = r.bytes()?;
+        if !typ.iter().all(u8::is_ascii_alphabetic) {
+            return Err(Malformed("bad PNG chunk type"));
+        }
+        if first && &typ != b"IHDR" {
+            return Err(Malformed("PNG doesn't start with IHDR"));
+        }
+        first = false;
+        let mut crc = crc32fast::Hasher::new();
+        crc.update(&typ);
+        r.read_with(len as u64, |data| crc.update(data))?;
+        if be_u32(r)? != crc.finalize() {
+            return Err(Malformed("bad PNG chunk checksum"));
+        }
+        match &typ {
+            b"IDAT" => has_data = true,
+            b"IEND" => break,
+            _ => (),
+        }
+    }
+    if !has_data {
+        return Err(Malformed("PNG without image data"));
+    }
+    Ok(())
+}
+
+/// A marker, after any fill bytes.
+fn jpeg_marker(r: &mut Reader) -> Result<u8, ValidateErr> {
+    if r.byte()? != 0xFF {
+        return Err(Malformed("expected a JPEG marker"));
+    }
+    loop {
+        match r.byte()? {
+            0xFF => continue,
+            m => return Ok(m),
+        }
+    }
+}
+
+/// Skip compressed data after a start of scan, returns the marker ending it.
+fn jpeg_scan(r: &mut Reader) -> Result<u8, ValidateErr> {
+    loop {
+        r.skip_past_ff()?;
+        let mut m = r.byte()?;
+        while m == 0xFF {
+            m = r.byte()?;
+        }
+        // a stuffed 0xFF byte, or a restart marker within the scan.
+        if !matches!(m, 0x00 | 0xD0..=0xD7) {
+            return Ok(m);
+        }
+    }
+}
+
+/// Segments and scans from SOI to EOI.
+fn jpeg(r: &mut Reader) -> Result<(), ValidateErr> {
+    if r.bytes()? != [0xFF, 0xD8] {
+        return Err(Malformed("not a JPEG"));
+    }
+    let mut marker = jpeg_marker(r)?;
+    loop {
+        match marker {
+            // EOI
+            0xD9 => return Ok(()),
+            // markers without a length.
+            0x01 | 0xD0..=0xD7 => marker = jpeg_marker(r)?,
+            0x00 | 0xD8 => return Err(Malformed("unexpected JPEG marker")),
+            _ => {
+                let len = be_u16(r)?;
+                if len < 2 {
+                    return Err(Malformed("bad JPEG segment length"));
+                }
+                r.skip(len as u64 - 2)?;
+                // SOS
+                marker = if marker == 0xDA {
+                    jpeg_scan(r)?
+                } else {
+                    jpeg_marker(r)?
+                };
+            }
+        }
+    }
+}
+
+fn gif_sub_blocks(r: &mut Reader) -> Result<(), ValidateErr> {
+    loop {
+        match r.byte()? {
+            0 => return Ok(()),
+            n => r.skip(n as u64)?,
+        }
+    }
+}
+
+fn gif_color_table(r: &mut Reader, flags: u8) -> Result<(), ValidateErr> {
+    if flags & 0x80 != 0 {
+        r.skip(3 << ((flags & 7) + 1))?;
+    }
+    Ok(())
+}
+
+/// Extensions and images up to the trailer.
+fn gif(r: &mut Reader) -> Result<(), ValidateErr> {
+    if !matches!(&r.bytes()?, b"GIF87a" | b"GIF89a") {
+        return Err(Malformed("not a GIF"));
+    }
+    let screen: [u8; 7] = r.bytes()?;
+    gif_color_table(r, screen[4])?;
+    let mut images = 0;
+    loop {
+        match r.byte()? {
+            // extension, then its label.
+            0x21 => {
+                r.byte()?;
+                gif_sub_blocks(r)?;
+            }
+            // image descriptor
+            0x2C => {
+                let desc: [u8; 9] = r.bytes()?;
+                gif_color_table(r, desc[8])?;
+                if !(2..=12).contains(&r.byte()?) {
+                    return Err(Malformed("bad GIF code size"));
+                }
+                gif_sub_blocks(r)?;
+                images += 1;
+            }
+            // trailer
+            0x3B => break,
+            _ => return Err(Malformed("unknown GIF block")),
+        }
+    }
+    if images == 0 {
+        return Err(Malformed("GIF without images"));
+    }
+    Ok(())
+}
+
+/// Chunks filling exactly the size in the RIFF header, e.g. WebP and WAV.
+fn riff(r: &mut Reader) -> Result<(), ValidateErr> {
+    if &r.bytes()? != b"RIFF" {
+        return Err(Malformed("not a RIFF file"));
+    }
+    let size = le_u32(r)? as u64;
+    if size < 4 {
+        return Err(Malformed("bad RIFF size"));
+    }
+    let end = r.pos + size;
+    let form: [u8; 4] = r.bytes()?;
+    let mut first = true;
+    while r.pos < end {
+        let id: [u8; 4] = r.bytes()?;
+        if !id.iter().all(|c| (0x20..0x7F).contains(c)) {
+            return Err(Malformed("bad RIFF chunk id"));
+        }
+        if first && &form == b"WEBP" && !matches!(&id, b"VP8 " | b"VP8L" | b"VP8X") {
+            return Err(Malformed("WebP doesn't start with an image"));
+        }
+        first = false;
+        let len = le_u32(r)? as u64;
+        // chunks are padded to an even size, though some writers leave it off the last one.
+        let pad = len & 1;
+        let len = if r.pos + len + pad > end {
+            len
+        } else {
+            len + pad
+        };
+        if r.pos + len > end {
+            return Err(if end > r.len {
+                Truncated
+            } else {
+                Malformed("RIFF chunk past the end")
+            });
+        }
+        r.skip(len)?;
+    }
+    if first {
+        return Err(Malformed("RIFF without chunks"));
+    }
+    Ok(())
+}
+
+/// Top level boxes covering the whole file, e.g. MP4, MOV, HEIF and AVIF.
+fn bmff(r: &mut Reader) -> Result<(), ValidateErr> {
+    let mut has_media = false;
+    while !r.at_end() {
+        let size = be_u32(r)? as u64;
+        let typ: [u8; 4] = r.bytes()?;
+        if !typ.iter().all(|c| (0x20..0x7F).contains(c)) {
+            return Err(Malformed("bad box type"));
+        }
+        let (size, header) = match size {
+            // the last box may run to the end of the file.
+            0 => (r.remaining() + 8, 8),
+            1 => (u64::from_be_bytes(r.bytes()?), 16),
+            size => (size, 8),
+        };
+        if size < header {
+            return Err(Malformed("bad box size"));
+        }
+        // a movie, or HEIF items.
+        has_media |= matches!(&typ, b"moov" | b"meta");
+        r.skip(size - header)?;
+    }
+    if !has_media {
+        return Err(Malformed("no moov or meta box"));
+    }
+    Ok(())
+}
+
+/// An EBML element ID or size. Returns None for an unknown size.
+fn ebml_vint(r: &mut Reader, keep_marker: bool) -> Result<Option<u64>, ValidateErr> {
+    let first = r.byte()?;
+    let len = first.leading_zeros() + 1;
+    if len > 8 {
+        return Err(Malformed("bad EBML number"));
+    }
+    let mut v = if keep_marker {
+        first as u64
+    } else {
+        first as u64 & (0xFF >> len)
+    };
+    let mut all_ones = v == 0xFF >> len;
+    for _ in 1..len {
+        let b = r.byte()?;
+        all_ones &= b == 0xFF;
+        v = v << 8 | b as u64;
+    }
+    Ok((keep_marker || !all_ones).then_some(v))
+}
+
+const EBML_HEADER: u64 = 0x1A45DFA3;
+const EBML_SEGMENT: u64 = 0x18538067;
+
+/// The EBML header and segments of a WebM or Matroska file.
+fn ebml(r: &mut Reader) -> Result<(), ValidateErr> {
+    let mut first = true;
+    let mut segments = 0;
+    while !r.at_end() {
+        let id = ebml_vint(r, true)?.unwrap_or_default();
+        if first && id != EBML_HEADER {
+            return Err(Malformed("not an EBML file"));
+        }
+        first = false;
+        match ebml_vint(r, false)? {
+            Some(size) => r.skip(size)?,
+            // e.g. live recordings, which can't know the size up front.
+            None if id == EBML_SEGMENT => r.skip(r.remaining())?,
+            None => return Err(Malformed("unknown size outside a segment")),
+        }
+        segments += (id == EBML_SEGMENT) as usize;
+    }
+    if segments == 0 {
+        return Err(Malformed("no Matroska segment"));
+    }
+    Ok(())
+}
+
+/// Ogg pages back to back.
+fn ogg(r: &mut Reader) -> Result<(), ValidateErr> {
+    while !r.at_end() {
+        let header: [u8; 27] = r.bytes()?;
+        if &header[..5] != b"OggS\0" {
+            return Err(Malformed("bad Ogg page"));
+        }
+        let mut len = 0;
+        for _ in 0..header[26] {
+            len += r.byte()? as u64;
+        }
+        r.skip(len)?;
+    }
+    Ok(())
+}
+
+/// The size in the header, and pixels that start within it.
+fn bmp(r: &mut Reader) -> Result<(), ValidateErr> {
+    let header: [u8; 14] = r.bytes()?;
+    let size = u32::from_le_bytes(header[2..6].try_into().unwrap()) as u64;
+    let offset = u32::from_le_bytes(header[10..14].try_into().unwrap()) as u64;
+    if &header[..2] != b"BM" || offset >= r.len {
+        return Err(Malformed("bad BMP header"));
+    }
+    let size = match size {
+        // writers may leave it out of uncompressed images, which end after their pixels.
+        0 => offset
+            .checked_add(bmp_pixels(r)?)
+            .ok_or(Malformed("bad BMP dimensions"))?,
+        size => size,
+    };
+    match size {
+        size if size < r.pos => Err(Malformed("bad BMP size")),
+        size if size > r.len => Err(Truncated),
+        size => r.skip(size - r.pos),
+    }
+}
+
+/// The size of the pixels of an uncompressed BMP, from the header after the file header.
+fn bmp_pixels(r: &mut Reader) -> Result<u64, ValidateErr> {
+    let le_u16 = |b: &[u8]| u16::from_le_bytes(b.try_into().unwrap()) as u64;
+    let le_i32 = |b: &[u8]| i32::from_le_bytes(b.try_into().unwrap()).unsigned_abs() as u64;
+    let (width, height, bpp) = match le_u32(r)? {
+        // BITMAPCOREHEADER, always uncompressed.
+        12 => {
+            let core: [u8; 8] = r.bytes()?;
+            (le_u16(&core[..2]), le_u16(&core[2..4]), le_u16(&core[6..]))
+        }
+        40.. => {
+            let info: [u8; 16] = r.bytes()?;
+            // BI_RGB, BI_BITFIELDS and BI_ALPHABITFIELDS, the others are compressed.
+            if !matches!(
+                u32::from_le_bytes(info[12..].try_into().unwrap()),
+                0 | 3 | 6
+            ) {
+                return Err(Malformed("compressed BMP without a size"));
+            }
+            // a negative height is a top-down image.
+            (
+                le_i32(&info[..4]),
+                le_i32(&info[4..8]),
+                le_u16(&info[10..12]),
+            )
+        }
+        _ => return Err(Malformed("bad BMP info header")),
+    };
+    // rows are padded to 4 bytes.
+    width
+        .checked_mul(bpp)
+        .map(|bits| bits.div_ceil(32) * 4)
+        .and_then(|row| row.checked_mul(height))
+        .ok_or(Malformed("bad BMP dimensions"))
+}
+
+type Check = fn(&mut Reader) -> Result<(), ValidateErr>;
+
+fn check_for(ext: &str) -> Option<Check> {
+    Some(match ext {
+        "png" | "apng" => png,
+        "jpg" => jpeg,
+        "gif" => gif,
+        "webp" | "wav" => riff,
+        "mp4" | "m4v" | "m4a" | "mov" | "3gp" | "3g2" | "heic" | "heif" | "avif" => bmff,
+        "webm" | "mkv" => ebml,
+        "ogg" | "opus" | "ogv" => ogg,
+        "bmp" => bmp,
+        _ => return None,
+    })
+}
+
+/// Whether [`validate`] knows how to check ext files, stores that validate take nothing else.
+pub fn can_validate(ext: &str) -> bool {
+    check_for(ext).is_some()
+}
+
+/// Walk a whole upload, making sure it is one well formed file of the type its extension says,
+/// with nothing after it. Formats without a check here fail, see [`can_validate`].
+pub fn validate(ext: &str, file: File) -> Result<(), ValidateErr> {
+    let check = check_for(ext).ok_or_else(|| ValidateErr::Unchecked(ext.to_owned()))?;
+    let len = file.metadata()?.len();
+    let mut r = Reader {
+        r: BufReader::with_capacity(64 * 1024, file),
+        pos: 0,
+        len,
+    };
+    check(&mut r)?;
+    r.finish()
+}
+
+/// [`validate`] a staged upload without blocking the runtime.
+pub async fn validate_file(ext: &str, path: &Path) -> Result<(), ValidateErr> {
+    let (ext, path): (String, PathBuf) = (ext.to_owned(), path.to_owned());
+    tokio::task::spawn_blocking(move || validate(&ext, File::open(path)?))
+        .await
+        .map_err(|e| ValidateErr::Io(io::Error::other(e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+    use crate::models::fixtures::{
+        HTML, IDAT, IHDR, ZIP, bmff_box, ebml_element, png_chunk, png_file, riff_file,
+    };
+
+    fn check(ext: &str, bytes: &[u8]) -> Result<(), ValidateErr> {
+        let path = std::env::temp_dir().join(format!(
+            "imageshare-validate-{:016x}",
+            rand::rng().random::<u64>()
+        ));
+        std::fs::write(&path, bytes).unwrap();
+        let res = validate(ext, File::open(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+        res
+    }
+
+    /// Every way a file can be broken, checked against a good one.
+    #[track_caller]
+    fn assert_checks(ext: &str, good: &[u8]) {
+        assert!(check(ext, good).is_ok(), "{ext}");
+        let truncated = check(ext, &good[..good.len() - 3]);
+        assert!(matches!(truncated, Err(Truncated)), "{ext}: {truncated:?}");
+        assert!(check(ext, &good[..good.len() / 2]).is_err(), "{ext}");
+        for trailer in [ZIP, HTML] {
+            let padded = check(ext, &[good, trailer].concat());
+            assert!(padded.is_err(), "{ext}");
+        }
+    }
+
+    #[test]
+    fn png() {
+        let good = png_file(&[
+            png_chunk(b"IHDR", &IHDR),
+            png_chunk(b"IDAT", IDAT),
+            png_chunk(b"IEND", &[]),
+        ]);
+        assert_checks("png", &good);
+        assert!(matches!(
+            check("png", &[&good, ZIP].concat()),
+            Err(ValidateErr::Trailing(n)) if n == ZIP.len() as u64
+        ));
+        assert!(matches!(
+            check("png", &[&good, HTML].concat()),
+            Err(ValidateErr::Trailing(n)) if n == HTML.len() as u64
+        ));
+
+        let mut bad_crc = good.clone();
+        bad_crc[8 + 8] ^= 1;
+        assert!(matches!(check("png", &bad_crc), Err(Malformed(_))));
+        let mut bad_len = good.clone();
+        bad_len[8..12].copy_from_slice(&0x8000_0000u32.to_be_bytes());
+        assert!(matches!(check("png", &bad_len), Err(Malformed(_))));
+        let no_data = png_file(&[png_chunk(b"IHDR", &IHDR), png_chunk(b"IEND", &[])]);
+        assert!(matches!(check("png", &no_data), Err(Malformed(_))));
+        let no_ihdr = png_file(&[png_chunk(b"IDAT", &[0]), png_chunk(b"IEND", &[])]);
+        assert!(matches!(check("png", &no_ihdr), Err(Malformed(_))));
+    }
+
+    fn jpeg_file(app0_len: u16) -> Vec<u8> {
+        let mut b = vec![0xFF, 0xD8, 0xFF, 0xE0];
+        b.extend_from_slice(&app0_len.to_be_bytes());
+        b.extend_from_slice(b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0");
+        // start of scan, with a stuffed 0xFF and a restart marker in the data.
+        b.extend_from_slice(&[0xFF, 0xDA, 0, 8, 1, 1, 0, 0, 0x3F, 0]);
+        b.extend_from_slice(&[0x12, 0xFF, 0x00, 0x34, 0xFF, 0xD0, 0x56]);
+        b.extend_from_slice(&[0xFF, 0xD9]);
+        b
+    }
+
+    #[test]
+    fn jpeg() {
+        let good = jpeg_file(16);
+        assert_checks("jpg", &good);
+        assert!(matches!(
+            check("jpg", &[&good, ZIP].concat()),
+            Err(ValidateErr::Trailing(_))
+        ));
+        assert!(matches!(check("jpg", &jpeg_file(1)), Err(Malformed(_))));
+        // the segment swallows the scan, leaving no marker where one should be.
+        assert!(check("jpg", &jpeg_file(30)).is_err());
+        assert!(matches!(check("jpg", b"\xFF\xD8\xFF\xD9"), Ok(())));
+        assert!(matches!(
+            check("jpg", b"\xFF\xD9\xFF\xD8"),
+            Err(Malformed(_))
+        ));
+    }
+
+    fn gif_file(code_size: u8) -> Vec<u8> {
+        let mut b = b"GIF89a".to_vec();
+        // 1x1, with a global color table of two colors.
+        b.extend_from_slice(&[1, 0, 1, 0, 0x80, 0, 0]);
+        b.extend_from_slice(&[0, 0, 0, 0xFF, 0xFF, 0xFF]);
+        // a graphic control extension.
+        b.extend_from_slice(&[0x21, 0xF9, 4, 0, 0, 0, 0, 0]);
+        b.extend_from_slice(&[0x2C, 0, 0, 0, 0, 1, 0, 1, 0, 0]);
+        b.extend_from_slice(&[code_size, 2, 0x44, 0x01, 0]);
+        b.push(0x3B);
+        b
+    }
+
+    #[test]
+    fn gif() {
+        let good = gif_file(2);
+        assert_checks("gif", &good);
+        assert!(matches!(
+            check("gif", &[&good, HTML].concat()),
+            Err(ValidateErr::Trailing(_))
+        ));
+        assert!(matches!(check("gif", &gif_file(13)), Err(Malformed(_))));
+        let mut no_image = gif_file(2);
+        no_image.drain(no_image.len() - 16..no_image.len() - 1);
+        assert!(matches!(check("gif", &no_image), Err(Malformed(_))));
+        let mut bad_block = gif_file(2);
+        let last = bad_block.len() - 1;
+        bad_block[last] = 0x42;
+        assert!(matches!(check("gif", &bad_block), Err(Malformed(_))));
+    }
+
+    #[test]
+    fn riff() {
+        let good = riff_file(b"WEBP", &[(b"VP8L", &[0x2F, 0, 0, 0, 0])]);
+        assert_checks("webp", &good);
+        assert!(matches!(
+            check("webp", &[&good, ZIP].concat()),
+            Err(ValidateErr::Trailing(_))
+        ));
+        // the last chunk's padding may be left off.
+        let mut unpadded = good[..good.len() - 1].to_vec();
+        unpadded[4..8].copy_from_slice(&17u32.to_le_bytes());
+        assert!(check("webp", &unpadded).is_ok());
+
+        let mut bad_len = good.clone();
+        bad_len[16..20].copy_from_slice(&100u32.to_le_bytes());
+        assert!(matches!(check("webp", &bad_len), Err(Malformed(_))));
+        let mut bad_size = good.clone();
+        bad_size[4..8].copy_from_slice(&2u32.to_le_bytes());
+        assert!(matches!(check("webp", &bad_size), Err(Malformed(_))));
+        let exif_first = riff_file(b"WEBP", &[(b"EXIF", &[0; 4]), (b"VP8L", &[0; 5])]);
+        assert!(matches!(check("webp", &exif_first), Err(Malformed(_))));
+        let wav = riff_file(b"WAVE", &[(b"fmt ", &[0; 16]), (b"data", &[0; 8])]);
+        assert_checks("wav", &wav);
+    }
+
+    #[test]
+    fn bmff() {
+        let ftyp = bmff_box(b"ftyp", b"isom\0\0\0\0isom");
+        let good = [
+            ftyp.clone(),
+            bmff_box(b"moov", &bmff_box(b"mvhd", &[0; 100])),
+            bmff_box(b"mdat", &[0; 64]),
+        ]
+        .concat();
+        assert_checks("mp4", &good);
+
+        let bad_size = [ftyp.clone(), 4u32.to_be_bytes().to_vec(), b"moov".to_vec()].concat();
+        assert!(matches!(check("mp4", &bad_size), Err(Malformed(_))));
+        let no_moov = [ftyp.clone(), bmff_box(b"mdat", &[0; 64])].concat();
+        assert!(matches!(check("mp4", &no_moov), Err(Malformed(_))));
+
+        // a 64-bit size, and a last box running to the end.
+        let mut large = 1u32.to_be_bytes().to_vec();
+        large.extend_from_slice(b"meta");
+        large.extend_from_slice(&24u64.to_be_bytes());
+        large.extend_from_slice(&[0; 8]);
+        let to_end = [
+            ftyp.clone(),
+            large,
+            vec![0, 0, 0, 0],
+            b"mdat".to_vec(),
+            vec![0; 9],
+        ]
+        .concat();
+        assert!(check("heic", &to_end).is_ok());
+        let mut huge = ftyp.clone();
+        huge.extend_from_slice(&1u32.to_be_bytes());
+        huge.extend_from_slice(b"moov");
+        huge.extend_from_slice(&u64::MAX.to_be_bytes());
+        assert!(matches!(check("mp4", &huge), Err(Truncated)));
+    }
+
+    #[test]
+    fn ebml() {
+        let header = ebml_element(
+            &[0x1A, 0x45, 0xDF, 0xA3],
+            &ebml_element(&[0x42, 0x82], b"webm"),
+        );
+        let segment = ebml_element(&[0x18, 0x53, 0x80, 0x67], &[0xEC, 0x84, 0, 0, 0, 0]);
+        let good = [header.clone(), segment.clone()].concat();
+        assert_checks("webm", &good);
+
+        let bad_vint = [header.clone(), vec![0x18, 0x53, 0x80, 0x67, 0x00]].concat();
+        assert!(matches!(check("webm", &bad_vint), Err(Malformed(_))));
+        assert!(matches!(check("webm", &header), Err(Malformed(_))));
+        assert!(matches!(check("mkv", &segment), Err(Malformed(_))));
+        // a live recording, which runs to the end.
+        let unknown = [header, vec![0x18, 0x53, 0x80, 0x67, 0xFF, 0xEC, 0x80]].concat();
+        assert!(check("mkv", &unknown).is_ok());
+    }
+
+    fn ogg_page(lacing: &[u8]) -> Vec<u8> {
+        let mut page = b"OggS\0\x02".to_vec();
+        page.extend_from_slice(&[0; 20]);
+        page.push(lacing.len() as u8);
+        page.extend_from_slice(lacing);
+        let len: usize = lacing.iter().map(|&l| l as usize).sum();
+        page.extend(std::iter::repeat_n(0x4F, len));
+        page
+    }
+
+    #[test]
+    fn ogg() {
+        let good = [ogg_page(&[19]), ogg_page(&[255, 10])].concat();
+        assert_checks("opus", &good);
+        let mut bad_len = good.clone();
+        bad_len[27] = 200;
+        assert!(check("ogg", &bad_len).is_err());
+        let mut bad_magic = good.clone();
+        bad_magic[3] = b'Z';
+        assert!(matches!(check("ogg", &bad_magic), Err(Malformed(_))));
+    }
+
+    /// A 1x1 24-bit BMP.
+    fn bmp_file(size: u32, offset: u32, compression: u32) -> Vec<u8> {
+        let mut b = b"BM".to_vec();
+        b.extend_from_slice(&size.to_le_bytes());
+        b.extend_from_slice(&[0; 4]);
+        b.extend_from_slice(&offset.to_le_bytes());
+        b.extend_from_slice(&40u32.to_le_bytes());
+        b.extend_from_slice(&1i32.to_le_bytes());
+        b.extend_from_slice(&(-1i32).to_le_bytes());
+        b.extend_from_slice(&[1, 0, 24, 0]);
+        b.extend_from_slice(&compression.to_le_bytes());
+        b.extend_from_slice(&[0; 20]);
+        // one pixel, padded to 4 bytes.
+        b.extend_from_slice(&[0xFF, 0, 0, 0]);
+        b
+    }
+
+    #[test]
+    fn bmp() {
+        let good = bmp_file(58, 54, 0);
+        assert!(check("bmp", &good).is_ok());
+        assert!(matches!(check("bmp", &bmp_file(60, 54, 0)), Err(Truncated)));
+        assert!(matches!(
+            check("bmp", &[&good, ZIP].concat()),
+            Err(ValidateErr::Trailing(n)) if n == ZIP.len() as u64
+        ));
+        assert!(matches!(
+            check("bmp", &bmp_file(10, 54, 0)),
+            Err(Malformed(_))
+        ));
+        assert!(matches!(
+            check("bmp", &bmp_file(58, 58, 0)),
+            Err(Malformed(_))
+        ));
+
+        // uncompressed images may leave the size out, they end after their pixels.
+        let no_size = bmp_file(0, 54, 0);
+        assert!(check("bmp", &no_size).is_ok());
+        assert!(matches!(
+            check("bmp", &[&no_size, HTML].concat()),
+            Err(ValidateErr::Trailing(n)) if n == HTML.len() as u64
+        ));
+        assert!(matches!(check("bmp", &no_size[..57]), Err(Truncated)));
+        // RLE8
+        assert!(matches!(
+            check("bmp", &bmp_file(0, 54, 1)),
+            Err(Malformed(_))
+        ));
+        assert!(check("bmp", &bmp_file(58, 54, 1)).is_ok());
+    }
+
+    #[test]
+    fn unchecked() {
+        let mp3 = [b"ID3\x04\0\0\0\0\0\0".as_slice(), HTML].concat();
+        assert!(matches!(check("mp3", &mp3), Err(ValidateErr::Unchecked(_))));
+        assert!(!can_validate("jxl"));
+        assert!(can_validate("apng"));
+    }
+}
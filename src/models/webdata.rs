@@ -11,11 +11,72 @@
 // WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
 // ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
 // OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+use std::{
+    ops::Deref,
+    sync::{Arc, RwLock},
+};
+
+use http::{HeaderMap, header::AUTHORIZATION};
+use sha2::{Digest, Sha256};
+
 use crate::config::StorageState;
 
 pub struct WebData {
-    pub image: StorageState,
-    pub paste: StorageState,
+    /// image, paste, then any named stores.
+    pub stores: Vec<StorageState>,
     /// The link prefix to send in replies to users, e.g. "https://images.ghetty.space"
-    pub link_prefix: String,
+    /// Swapped on reload.
+    pub link_prefix: RwLock<String>,
+    /// SHA-256 of each token trusted uploaders may send as "Authorization: Bearer <token>".
+    pub auth_tokens: Vec<[u8; 32]>,
+}
+
+impl WebData {
+    pub fn is_authorized(&self, headers: &HeaderMap) -> bool {
+        let Some(token) = headers
+            .get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+        else {
+            return false;
+        };
+        // comparing digests doesn't leak how much of a token matched.
+        let digest: [u8; 32] = Sha256::digest(token.trim()).into();
+        self.auth_tokens.contains(&digest)
+    }
+}
+
+/// State for the routes of a single store.
+#[derive(Clone)]
+pub struct StoreData {
+    webdata: Arc<WebData>,
+    idx: usize,
+}
+
+impl StoreData {
+    pub fn new(webdata: Arc<WebData>, idx: usize) -> Self {
+        Self { webdata, idx }
+    }
+
+    pub fn storage(&self) -> &StorageState {
+        &self.webdata.stores[self.idx]
+    }
+
+    /// e.g. https://images.ghetty.space/i/abc123.png
+    pub fn link(&self, fname: &str) -> String {
+        self.url(&format!("{}/{fname}", self.storage().route()))
+    }
+
+    /// e.g. /upload/tus -> https://images.ghetty.space/upload/tus
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.webdata.link_prefix.read().unwrap())
+    }
+}
+
+impl Deref for StoreData {
+    type Target = WebData;
+
+    fn deref(&self) -> &Self::Target {
+        &self.webdata
+    }
 }
@@ -0,0 +1,72 @@
+// Copyright (c) 2026, Anthony DeDominic <adedomin@gmail.com>
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+use std::{sync::Arc, time::Duration};
+
+use tokio::task::JoinHandle;
+
+use crate::models::webdata::WebData;
+
+const REAP_INTERVAL_MIN: Duration = Duration::from_secs(60);
+const REAP_INTERVAL_MAX: Duration = Duration::from_secs(60 * 60);
+/// How soon files deleted by someone else stop counting against a store's limits.
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+fn spawn_reaper(webdata: Arc<WebData>, idx: usize) -> JoinHandle<()> {
+    let period = match webdata.stores[idx].get_max_age() {
+        // check often enough that files don't overstay their welcome by much.
+        Some(max_age) => (max_age / 8)
+            .clamp(REAP_INTERVAL_MIN, REAP_INTERVAL_MAX)
+            .min(RECONCILE_INTERVAL),
+        None => RECONCILE_INTERVAL,
+    };
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let store = &webdata.stores[idx];
+            if let Err(e) = store.reap().await {
+                eprintln!(
+                    "WARN: failed to reap expired files in {}: {e}",
+                    store.name()
+                );
+            }
+            if let Err(e) = store.expire_tus().await {
+                eprintln!(
+                    "WARN: failed to expire resumable uploads in {}: {e}",
+                    store.name()
+                );
+            }
+            match store.reconcile().await {
+                Ok(0) => (),
+                Ok(gone) => eprintln!(
+                    "WARN: {gone} files in {} were deleted by something else.",
+                    store.name()
+                ),
+                Err(e) => eprintln!("WARN: failed to reconcile {}: {e}", store.name()),
+            }
+        }
+    })
+}
+
+/// Spawn background tasks which delete expired files from every store with a max_age,
+/// notice files deleted outside of imageshare-rs in stores that keep track of them,
+/// and throw away abandoned resumable uploads.
+pub fn start_reapers(webdata: &Arc<WebData>) {
+    for (idx, store) in webdata.stores.iter().enumerate() {
+        if store.get_max_age().is_some() || store.tracks_uploads() || store.tus_path().is_some() {
+            spawn_reaper(webdata.clone(), idx);
+        }
+    }
+}
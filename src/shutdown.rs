@@ -1,3 +1,13 @@
+#[cfg(unix)]
+use std::sync::Arc;
+
+#[cfg(unix)]
+use crate::{
+    config::{Config, load_config},
+    middleware::ratelim::BucketRatelim,
+    models::webdata::WebData,
+};
+
 #[cfg(unix)]
 pub async fn shutdown() {
     use tokio::signal::{
@@ -13,6 +23,37 @@ pub async fn shutdown() {
     eprintln!("WARN: Shutting down.");
 }
 
+/// Re-read the config on SIGHUP and apply what can change without a restart.
+#[cfg(unix)]
+pub fn reload_on_hangup(mut config: Config, webdata: Arc<WebData>, ratelim: Option<BucketRatelim>) {
+    use tokio::signal::unix::{self, SignalKind};
+
+    let mut sighup = unix::signal(SignalKind::hangup()).unwrap();
+    tokio::spawn(async move {
+        while sighup.recv().await.is_some() {
+            eprintln!("WARN: SIGHUP: reloading {}.", config.path().display());
+            let new = match load_config(Some(config.path().to_owned())) {
+                Ok(new) => new,
+                Err(e) => {
+                    eprintln!("WARN: reload failed, keeping the running config: {e}");
+                    continue;
+                }
+            };
+            for key in new.unknown_keys() {
+                eprintln!("WARN: unknown config key {key}, see check-config.");
+            }
+            if let (Some(limiter), Some(rl)) = (&ratelim, &new.ratelim)
+                && config.ratelim.as_ref() != Some(rl)
+            {
+                limiter.reload(rl.clone());
+            }
+            for setting in config.apply_live(new, &webdata) {
+                eprintln!("WARN: {setting} needs a restart, ignored.");
+            }
+        }
+    });
+}
+
 #[cfg(windows)]
 pub mod windows {
     use std::sync::OnceLock;
@@ -0,0 +1,80 @@
+// Copyright (c) 2026, Anthony DeDominic <adedomin@gmail.com>
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+use std::sync::Arc;
+
+use axum::{
+    Extension, Router,
+    body::{Body, Bytes},
+    extract::{DefaultBodyLimit, State},
+    routing::post,
+};
+use http::HeaderMap;
+use tower::ServiceBuilder;
+
+use crate::{
+    middleware::{attachment::Attachment, contentlen::HeaderSizeLim, ratelim::ClientIp},
+    models::{
+        api::ApiError,
+        webdata::{StoreData, WebData},
+    },
+    web::image::{self, not_accepted, reserve_for_body, stream_upload},
+};
+
+const FILE_NAME: &str = "X-File-Name";
+
+/// The lowercased extension of the name the uploader sent, or bin if there isn't a sensible one.
+fn file_ext(headers: &HeaderMap) -> String {
+    headers
+        .get(FILE_NAME)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|name| name.rsplit(['/', '\\']).next()?.rsplit_once('.'))
+        .map(|(_, ext)| ext)
+        .filter(|ext| {
+            (1..=16).contains(&ext.len()) && ext.bytes().all(|c| c.is_ascii_alphanumeric())
+        })
+        .map(|ext| ext.to_ascii_lowercase())
+        .unwrap_or_else(|| "bin".to_owned())
+}
+
+async fn upload_file(
+    State(store): State<StoreData>,
+    ip: Option<Extension<ClientIp>>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<ApiError, ApiError> {
+    let storage = store.storage();
+    reserve_for_body(storage, &headers).await?;
+    let ext = file_ext(&headers);
+    let body = body.into_data_stream();
+    if !storage.accepts(&ext) {
+        return Err(not_accepted(storage, &ext, body).await);
+    }
+    stream_upload(&store, ip, &headers, "file", &ext, Bytes::new(), body).await
+}
+
+pub fn upload_route(store: StoreData) -> Router<Arc<WebData>> {
+    let storage = store.storage();
+    Router::new()
+        .route(storage.upload_path(), post(upload_file))
+        .layer(
+            ServiceBuilder::new()
+                .layer(DefaultBodyLimit::disable())
+                .layer(HeaderSizeLim::from(storage.max_siz_handle())),
+        )
+        .with_state(store)
+}
+
+pub fn serve_route(store: StoreData) -> Router<Arc<WebData>> {
+    image::serve_route(store, Some(Attachment))
+}
@@ -11,48 +11,106 @@
 // WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
 // ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
 // OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
-use std::{path::Path, sync::Arc};
+use std::sync::Arc;
 
-use crate::middleware::contentlen::HeaderSizeLim;
+use crate::config::{StorageState, Vanity};
+use crate::middleware::attachment::Attachment;
+use crate::middleware::contentlen::{HeaderSizeLim, is_form};
+use crate::middleware::csrf::HeaderCsrf;
 use crate::middleware::earlyretfut::ConsumeBody;
-use crate::models::dropfs::{DropFsGuard, background_rm_file};
-use crate::models::webdata::WebData;
-use crate::models::{api::ApiError, mime::detect_ext};
+use crate::middleware::ratelim::ClientIp;
+#[cfg(feature = "serve-files")]
+use crate::middleware::touch::Touch;
+use crate::models::dropfs::DropFsGuard;
+use crate::models::meta::UploadMeta;
+#[cfg(feature = "serve-files")]
+use crate::models::mime::content_type;
+use crate::models::strip::strip_file;
+use crate::models::validate::validate_file;
+use crate::models::webdata::{StoreData, WebData};
+use crate::models::{
+    api::{ApiError, ApiResults},
+    mime::{SNIFF_LEN, detect_ext},
+};
 use axum::body::{Body, BodyDataStream};
+use axum::handler::Handler;
 use axum::{
-    Router,
+    Extension, Router,
     body::Bytes,
-    extract::{DefaultBodyLimit, State},
-    http::StatusCode,
-    routing::post,
-};
-use futures_util::stream::StreamExt;
-use tokio::{
-    fs::File,
-    io::{AsyncWriteExt, BufWriter},
+    extract::{DefaultBodyLimit, Path as UrlPath, State},
+    http::{
+        HeaderMap, StatusCode,
+        header::{CONTENT_LENGTH, CONTENT_TYPE},
+    },
+    response::{IntoResponse, Response},
+    routing::{delete, post},
 };
+use futures_util::stream::{Stream, StreamExt};
+use multer::{Constraints, Field, Multipart, SizeLimit};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncWriteExt, BufWriter};
 use tower::ServiceBuilder;
 
-async fn get_ext(
-    mut body: BodyDataStream,
-) -> Result<(BodyDataStream, Bytes, &'static str), ApiError> {
-    let initial = body
+/// Most uploaders and HTML forms send this many files at most in one request.
+const MAX_PARTS: usize = 16;
+
+/// The bytes of one upload: a whole request body, or one file of a form.
+pub trait UploadBody: Stream<Item = Result<Bytes, Self::Error>> + Unpin + Send {
+    type Error: Into<ApiError>;
+
+    /// Read and drop what's left, true if the connection can be kept afterwards.
+    fn discard(self) -> impl Future<Output = bool> + Send;
+}
+
+impl UploadBody for BodyDataStream {
+    type Error = axum::Error;
+
+    fn discard(self) -> impl Future<Output = bool> + Send {
+        ConsumeBody::new(self)
+    }
+}
+
+impl UploadBody for Field<'_> {
+    type Error = multer::Error;
+
+    /// Other files may follow, so the form is only usable if this one is read to the end.
+    async fn discard(mut self) -> bool {
+        while let Some(chunk) = self.next().await {
+            if chunk.is_err() {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+async fn get_ext<B: UploadBody>(mut body: B) -> Result<(B, Bytes, &'static str), ApiError> {
+    let mut initial = body
         .next()
         .await
         .ok_or(ApiError::new_with_status(
             StatusCode::UNPROCESSABLE_ENTITY,
             "No bytes read.",
         ))?
-        .map_err(ApiError::new)?;
-    // If we read less than 12 bytes, we should reject this request on principle of it being too slow or weird.
+        .map_err(Into::into)?;
+    // slow or chunked clients may send the start of a file in small pieces.
+    if initial.len() < SNIFF_LEN {
+        let mut buf = initial.to_vec();
+        while buf.len() < SNIFF_LEN
+            && let Some(chunk) = body.next().await
+        {
+            buf.extend_from_slice(&chunk.map_err(Into::into)?);
+        }
+        initial = buf.into();
+    }
     if let Some(ext) = detect_ext(&initial) {
         Ok((body, initial, ext))
     } else {
         // attempt to consume rest of body.
-        let done = ConsumeBody::new(body).await;
+        let done = body.discard().await;
         Err(ApiError::new_with_status(
             StatusCode::UNSUPPORTED_MEDIA_TYPE,
-            "Unsupported image or video format.",
+            "Unsupported image, video or audio format.",
         )
         .should_close_conn(!done))
     }
@@ -64,45 +122,320 @@ pub fn payload_too_large(typ: &'static str, lim: usize, close: bool) -> ApiError
         .should_close_conn(close)
 }
 
-async fn upload_img(State(webdata): State<Arc<WebData>>, body: Body) -> Result<ApiError, ApiError> {
-    let WebData {
-        link_prefix,
-        image: storage,
-        ..
-    } = webdata.as_ref();
-    let (mut body, initial_read, ext) = get_ext(body.into_data_stream()).await?;
-    let fname = storage.gen_new_fname(ext);
-    let mut upload = storage.get_base();
-    upload.push(&fname);
-    // if the file fails beyond this point, it will be stale in the FIFO. oh well.
-    if let Some(del) = storage.push(&upload) {
-        background_rm_file(del);
+const VANITY_NAME: &str = "X-Vanity-Name";
+
+/// The name the uploader asked for, if any and if they may have it.
+pub fn vanity_name(
+    webdata: &WebData,
+    storage: &StorageState,
+    headers: &HeaderMap,
+    ext: &str,
+) -> Result<Option<String>, ApiError> {
+    let Some(slug) = headers.get(VANITY_NAME) else {
+        return Ok(None);
+    };
+    match storage.vanity() {
+        Vanity::Off => {
+            return Err(ApiError::new_with_status(
+                StatusCode::FORBIDDEN,
+                "Vanity names are not allowed here.",
+            ));
+        }
+        Vanity::Auth if !webdata.is_authorized(headers) => {
+            return Err(ApiError::new_with_status(
+                StatusCode::UNAUTHORIZED,
+                "Vanity names require a valid Authorization header.",
+            ));
+        }
+        _ => (),
+    }
+    let slug = slug.to_str().unwrap_or_default();
+    // a leading letter or digit keeps names from looking like hidden files or flags.
+    if !(3..=64).contains(&slug.len())
+        || !slug.starts_with(|c: char| c.is_ascii_alphanumeric())
+        || !slug
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_')
+    {
+        return Err(ApiError::new_with_status(
+            StatusCode::BAD_REQUEST,
+            format!("{VANITY_NAME} must be 3 to 64 of a-z, A-Z, 0-9, - and _."),
+        ));
+    }
+    Ok(Some(format!("{slug}.{ext}")))
+}
+
+pub fn name_taken(fname: &str) -> ApiError {
+    ApiError::new_with_status(StatusCode::CONFLICT, format!("{fname} is already taken."))
+}
+
+/// Make sure there is room for the body before reading it.
+pub async fn reserve_for_body(storage: &StorageState, headers: &HeaderMap) -> Result<(), ApiError> {
+    // chunked uploads could be up to the limit.
+    let incoming = headers
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok()?.parse().ok())
+        .unwrap_or(storage.get_max_siz() as u64);
+    Ok(storage.reserve_space(incoming).await?)
+}
+
+pub async fn not_accepted<B: UploadBody>(storage: &StorageState, ext: &str, body: B) -> ApiError {
+    let done = body.discard().await;
+    ApiError::new_with_status(
+        StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        format!("The {} store doesn't take {ext} files.", storage.name()),
+    )
+    .should_close_conn(!done)
+}
+
+async fn upload_img(
+    State(store): State<StoreData>,
+    ip: Option<Extension<ClientIp>>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Response, ApiError> {
+    reserve_for_body(store.storage(), &headers).await?;
+    if is_form(&headers) {
+        return Ok(upload_form(&store, ip, &headers, body)
+            .await?
+            .into_response());
+    }
+    let res = upload_one(&store, ip, &headers, body.into_data_stream()).await?;
+    Ok(res.into_response())
+}
+
+async fn upload_one<B: UploadBody>(
+    store: &StoreData,
+    ip: Option<Extension<ClientIp>>,
+    headers: &HeaderMap,
+    body: B,
+) -> Result<ApiError, ApiError> {
+    let storage = store.storage();
+    let (body, initial_read, ext) = get_ext(body).await?;
+    if !storage.accepts(ext) {
+        return Err(not_accepted(storage, ext, body).await);
+    }
+    stream_upload(store, ip, headers, "image", ext, initial_read, body).await
+}
+
+/// Every file in a multipart/form-data body, e.g. from curl -F file=@a.png or an HTML form.
+async fn upload_form(
+    store: &StoreData,
+    ip: Option<Extension<ClientIp>>,
+    headers: &HeaderMap,
+    body: Body,
+) -> Result<ApiResults, ApiError> {
+    let boundary = headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| multer::parse_boundary(v).ok())
+        .ok_or(ApiError::new_with_status(
+            StatusCode::BAD_REQUEST,
+            "multipart/form-data without a boundary.",
+        ))?;
+    let lim = store.storage().get_max_siz() as u64;
+    // the per file limit also bounds inputs that aren't files.
+    let constraints = Constraints::new().size_limit(
+        SizeLimit::new()
+            .whole_stream(lim.saturating_mul(MAX_PARTS as u64))
+            .per_field(lim),
+    );
+    let mut form = Multipart::with_constraints(body.into_data_stream(), boundary, constraints);
+    let mut results = vec![];
+    loop {
+        let field = match form.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => {
+                results.push(e.into());
+                break;
+            }
+        };
+        // other inputs, e.g. a submit button.
+        if field.file_name().is_none() {
+            continue;
+        }
+        if results.len() == MAX_PARTS {
+            results.push(
+                ApiError::new_with_status(
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    format!("At most {MAX_PARTS} files per upload."),
+                )
+                .close_conn(),
+            );
+            break;
+        }
+        let res = upload_one(store, ip, headers, field)
+            .await
+            .unwrap_or_else(|e| e);
+        let broken = res.closes_conn();
+        results.push(res);
+        if broken {
+            break;
+        }
     }
+    if results.is_empty() {
+        return Err(ApiError::new_with_status(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "No files in the form.",
+        ));
+    }
+    Ok(ApiResults(results))
+}
 
-    let fguard = DropFsGuard::new(&upload);
+/// Write a streamed upload to staging, then publish it under a new or requested name.
+pub async fn stream_upload<B: UploadBody>(
+    store: &StoreData,
+    ip: Option<Extension<ClientIp>>,
+    headers: &HeaderMap,
+    typ: &'static str,
+    ext: &str,
+    initial_read: Bytes,
+    mut body: B,
+) -> Result<ApiError, ApiError> {
+    let storage = store.storage();
+    let vanity = vanity_name(store, storage, headers, ext).map_err(ApiError::close_conn)?;
+    if let Some(ref fname) = vanity
+        && let Ok(Some(_)) = storage.backend().stat(fname).await
     {
-        let max_siz = storage.get_max_siz();
-        let mut written: usize = 0;
-        // if we collide with file names, better to just overwrite.
-        let mut file = BufWriter::new(File::create(&upload).await?);
-        // write our mime detect read.
+        return Err(name_taken(fname).close_conn());
+    }
+    let (staged, file) = storage.create_staging().await?;
+    let fguard = DropFsGuard::new(&staged);
+    let max_siz = storage.get_max_siz();
+    let mut written: usize = 0;
+    let mut hasher = Sha256::new();
+    {
+        let mut file = BufWriter::new(file);
+        // write our mime detect read, if any.
         written += initial_read.len();
+        hasher.update(&initial_read);
         file.write_all(&initial_read).await?;
         while let Some(chunk) = body.next().await {
-            let chunk = chunk.map_err(ApiError::new)?;
+            let chunk = chunk.map_err(Into::into)?;
             written += chunk.len();
             // Technically forms can be sent with Transfer-Encoding: chunked.
             // So we must guard against large reads.
             if written > max_siz {
-                let done = ConsumeBody::new(body).await;
-                return Err(payload_too_large("image", max_siz, !done));
+                let done = body.discard().await;
+                return Err(payload_too_large(typ, max_siz, !done));
             }
+            hasher.update(&chunk);
             file.write_all(&chunk).await?;
         }
         file.flush().await?;
     }
+    if storage.validates() {
+        validate_file(ext, &staged).await?;
+    }
+    let (mut size, mut sha256) = (written as u64, hasher.finalize().into());
+    if storage.strips_metadata()
+        && let Some(stripped) = strip_file(ext, &staged).await?
+    {
+        (size, sha256) = stripped;
+    }
+    let meta = UploadMeta::new(
+        size,
+        ext,
+        &sha256,
+        ip.map(|Extension(ClientIp(ip))| storage.hash_ip(ip)),
+    );
+    if vanity.is_none()
+        && let Some(fname) = storage.find_dup(&meta.sha256).await
+    {
+        // the guard throws away our copy; the original belongs to someone else, so no token.
+        return Ok(ApiError::new_ok(store.link(&fname)));
+    }
+    let fname = match vanity {
+        Some(fname) => match storage.publish_as(&staged, &fname).await {
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                return Err(name_taken(&fname));
+            }
+            res => res.map(|_| fname)?,
+        },
+        None => storage.publish(&staged, ext).await?,
+    };
     fguard.defuse();
-    Ok(ApiError::new_ok(format!("{link_prefix}/i/{fname}")))
+    // only now do we know how much room the upload needs.
+    storage.record(&fname, &meta).await;
+    Ok(ApiError::new_ok(store.link(&fname))
+        .with_delete_token(storage.deletion_token(&fname, &meta)))
+}
+
+pub const DELETE_TOKEN: &str = "X-Delete-Token";
+
+/// Delete an upload if the request carries the deletion token we handed out for it.
+pub async fn delete_upload(
+    storage: &StorageState,
+    fname: &str,
+    headers: &HeaderMap,
+) -> Result<ApiError, ApiError> {
+    let token = headers
+        .get(DELETE_TOKEN)
+        .and_then(|v| v.to_str().ok())
+        .ok_or(ApiError::new_with_status(
+            StatusCode::UNAUTHORIZED,
+            format!("Missing {DELETE_TOKEN} header."),
+        ))?;
+    if !storage.check_deletion_token(fname, token).await {
+        return Err(ApiError::new_with_status(
+            StatusCode::FORBIDDEN,
+            "Invalid deletion token.",
+        ));
+    }
+    match storage.delete(fname).await {
+        Ok(()) => Ok(ApiError::new_ok("Deleted.")),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(ApiError::new_with_status(
+            StatusCode::NOT_FOUND,
+            "No such file.",
+        )),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Serve an upload out of a backend that isn't a local directory.
+#[cfg(feature = "serve-files")]
+pub async fn get_upload(
+    storage: &StorageState,
+    fname: &str,
+) -> Result<axum::response::Response, ApiError> {
+    let obj = match storage.backend().get(fname).await {
+        Ok(obj) => obj,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(ApiError::new_with_status(
+                StatusCode::NOT_FOUND,
+                "No such file.",
+            ));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let ext = fname
+        .rsplit_once('.')
+        .map(|(_, ext)| ext)
+        .unwrap_or_default();
+    Ok(axum::response::Response::builder()
+        .status(StatusCode::OK)
+        .header(http::header::CONTENT_TYPE, content_type(ext))
+        .header(http::header::CONTENT_LENGTH, obj.size)
+        .header(http::header::X_CONTENT_TYPE_OPTIONS, "nosniff")
+        .body(Body::from_stream(obj.body))
+        .unwrap())
+}
+
+#[cfg(feature = "serve-files")]
+async fn get_img(
+    State(store): State<StoreData>,
+    UrlPath(fname): UrlPath<String>,
+) -> Result<axum::response::Response, ApiError> {
+    get_upload(store.storage(), &fname).await
+}
+
+async fn delete_img(
+    State(store): State<StoreData>,
+    UrlPath(fname): UrlPath<String>,
+    headers: HeaderMap,
+) -> Result<ApiError, ApiError> {
+    delete_upload(store.storage(), &fname, &headers).await
 }
 
 #[cfg(not(feature = "serve-files"))]
@@ -120,30 +453,78 @@ location /i/ {
 "###;
 
 #[cfg(not(feature = "serve-files"))]
-async fn get_file_err() -> axum::response::Response {
+const ATTACHMENT_ERR_MSG: &str = r###"
+You are expected to use a Reverse Proxy to host imageshare if you disable the `serve-files` feature.
+To serve a file store, Please see the example nginx snippet:
+
+```nginx.conf
+# assumes a store named files, served at /f
+location /f/ {
+    types { }
+    default_type application/octet-stream;
+    add_header Content-Disposition attachment;
+    add_header X-Content-Type-Options nosniff;
+    alias /var/lib/imageshare-rs/files/;
+}
+```
+"###;
+
+#[cfg(not(feature = "serve-files"))]
+fn file_err(msg: &'static str) -> axum::response::Response {
     axum::response::Response::builder()
         .status(http::StatusCode::OK)
         .header(http::header::CONTENT_TYPE, "text/plain; charset=utf8")
-        .body(FILE_ERR_MSG.into())
+        .body(msg.into())
         .unwrap()
 }
 
-pub fn upload_route(lim: usize) -> Router<Arc<WebData>> {
-    Router::new().route("/upload", post(upload_img)).layer(
-        ServiceBuilder::new()
-            .layer(DefaultBodyLimit::disable())
-            .layer(HeaderSizeLim::from(lim)),
-    )
+pub fn upload_route(store: StoreData) -> Router<Arc<WebData>> {
+    let storage = store.storage();
+    Router::new()
+        .route(storage.upload_path(), post(upload_img))
+        .layer(
+            ServiceBuilder::new()
+                .layer(DefaultBodyLimit::disable())
+                .layer(HeaderSizeLim::from(storage.max_siz_handle()).with_form_parts(MAX_PARTS)),
+        )
+        .with_state(store)
 }
 
-pub fn serve_route<P: AsRef<Path>>(_p: P) -> Router<Arc<WebData>> {
-    let r = Router::new();
+/// Serve a store's uploads and take DELETEs for them.
+/// With an [`Attachment`] layer, browsers download them instead of displaying them.
+pub fn serve_route(store: StoreData, attachment: Option<Attachment>) -> Router<Arc<WebData>> {
+    // DELETE shares the path with the file server, so files are served as its fallback.
+    let del = delete(delete_img.layer(HeaderCsrf));
     #[cfg(feature = "serve-files")]
-    let r = r.nest_service(
-        "/i",
-        tower_http::services::ServeDir::new(_p).with_buf_chunk_size(256 * 1024),
-    );
+    let (del, r) = match store.storage().backend().local_dir() {
+        Some(dir) => {
+            let files = ServiceBuilder::new()
+                .option_layer(attachment)
+                .map_request(dir.map_request())
+                .service(
+                    tower_http::services::ServeDir::new(dir.path).with_buf_chunk_size(256 * 1024),
+                );
+            (
+                del.fallback_service(files.clone()),
+                Router::new().fallback_service(files),
+            )
+        }
+        None => (
+            del.get(get_img.layer(ServiceBuilder::new().option_layer(attachment))),
+            Router::new(),
+        ),
+    };
     #[cfg(not(feature = "serve-files"))]
-    let r = r.route("/i/{*any}", axum::routing::get(get_file_err));
-    r
+    let (del, r) = {
+        let msg = match attachment {
+            Some(_) => ATTACHMENT_ERR_MSG,
+            None => FILE_ERR_MSG,
+        };
+        let err = move || async move { file_err(msg) };
+        (del.fallback(err), Router::new().fallback(err))
+    };
+    let r = Router::new().nest(store.storage().route(), r.route("/{fname}", del));
+    #[cfg(feature = "serve-files")]
+    let r = r.layer(ServiceBuilder::new().option_layer(Touch::lru(&store)));
+    r.with_state(store)
 }
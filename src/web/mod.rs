@@ -18,16 +18,19 @@ use tokio::{net::TcpListener, task::JoinHandle};
 use tower::ServiceBuilder;
 
 use crate::{
-    config::Config,
+    config::{Config, Kind},
     middleware::{csrf::HeaderCsrf, ratelim::BucketRatelim},
-    models::webdata::WebData,
+    models::webdata::{StoreData, WebData},
+    reaper::start_reapers,
     shutdown::shutdown,
     web::uds::UdsErr,
 };
 
+mod file;
 mod image;
 mod paste;
 mod static_files;
+mod tus;
 mod uds;
 
 #[derive(Debug, thiserror::Error)]
@@ -40,23 +43,43 @@ pub enum WebErr {
     GenericIO(#[from] std::io::Error),
 }
 
-pub fn start_web(mut config: Config, webdata: Arc<WebData>) -> JoinHandle<Result<(), WebErr>> {
+pub fn start_web(config: Config, webdata: Arc<WebData>) -> JoinHandle<Result<(), WebErr>> {
     let bind_addr = config.get_bind_addr();
-    let ratelim = config.ratelim.take().map(BucketRatelim::from);
+    let ratelim = config.ratelim.clone().map(BucketRatelim::from);
     if !config.link_prefix.is_empty() {
         println!("Listening on {}", config.link_prefix);
     }
 
+    start_reapers(&webdata);
+    #[cfg(unix)]
+    crate::shutdown::reload_on_hangup(config, webdata.clone(), ratelim.clone());
+
+    let (mut uploads, mut serve) = (Router::new(), Router::new());
+    for (idx, storage) in webdata.stores.iter().enumerate() {
+        let store = StoreData::new(webdata.clone(), idx);
+        let (upload, files) = match storage.kind() {
+            Kind::Media => (
+                image::upload_route(store.clone()).merge(tus::upload_route(store.clone())),
+                image::serve_route(store.clone(), None).merge(tus::resume_route(store)),
+            ),
+            Kind::Text => (
+                paste::upload_route(store.clone()),
+                paste::serve_route(store),
+            ),
+            Kind::File => (file::upload_route(store.clone()), file::serve_route(store)),
+        };
+        uploads = uploads.merge(upload);
+        serve = serve.merge(files);
+    }
+
     let web = Router::<Arc<WebData>>::new()
-        .merge(image::upload_route(webdata.image.get_max_siz()))
-        .merge(paste::upload_route(webdata.paste.get_max_siz()))
+        .merge(uploads)
         .layer(
             ServiceBuilder::new()
                 .layer(HeaderCsrf)
                 .option_layer(ratelim),
         )
-        .merge(image::serve_route(webdata.image.get_base()))
-        .merge(paste::serve_route(webdata.paste.get_base()))
+        .merge(serve)
         .merge(static_files::routes())
         .with_state(webdata);
     let shutdown_h = shutdown();
@@ -11,60 +11,181 @@
 // WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
 // ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
 // OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
-use std::{path::Path, sync::Arc};
+use std::sync::Arc;
 
 use axum::{
-    Router,
-    extract::{DefaultBodyLimit, State, rejection::StringRejection},
-    routing::post,
+    Extension, Router,
+    body::Body,
+    extract::{DefaultBodyLimit, Path as UrlPath, State},
+    handler::Handler,
+    routing::{delete, post},
 };
-use http::StatusCode;
+use flate2::{Compression, write::GzEncoder};
+use futures_util::StreamExt;
+use http::{HeaderMap, StatusCode};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use tokio::io::AsyncWriteExt;
 use tower::ServiceBuilder;
 
 #[cfg(feature = "serve-files")]
-use crate::middleware::utf8textplain::Utf8TextPlain;
+use crate::{middleware::touch::Touch, web::image::get_upload};
 use crate::{
-    middleware::contentlen::HeaderSizeLim,
+    middleware::{
+        contentlen::HeaderSizeLim, csrf::HeaderCsrf, earlyretfut::ConsumeBody, ratelim::ClientIp,
+    },
     models::{
         api::ApiError,
-        dropfs::{DropFsGuard, background_rm_file},
-        webdata::WebData,
+        dropfs::DropFsGuard,
+        meta::UploadMeta,
+        webdata::{StoreData, WebData},
     },
-    web::image::payload_too_large,
+    web::image::{delete_upload, name_taken, payload_too_large, vanity_name},
+};
+#[cfg(feature = "serve-files")]
+use flate2::read::GzDecoder;
+#[cfg(feature = "serve-files")]
+use http::{
+    HeaderValue,
+    header::{ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH, VARY},
 };
+#[cfg(feature = "serve-files")]
+use std::io::Read;
 
-fn handle_paste(r: Result<String, StringRejection>, lim: usize) -> Result<String, ApiError> {
-    match r {
-        Ok(paste) => Ok(paste),
-        Err(e) if e.status() == StatusCode::PAYLOAD_TOO_LARGE => {
-            Err(payload_too_large("paste", lim, true))
+/// Read a whole paste, the limit is checked as we go since it can change on reload.
+async fn read_paste(body: Body, lim: usize) -> Result<String, ApiError> {
+    let mut body = body.into_data_stream();
+    let mut paste = vec![];
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(ApiError::new)?;
+        // Transfer-Encoding: chunked gets past the Content-Length check.
+        if paste.len() + chunk.len() > lim {
+            let done = ConsumeBody::new(body).await;
+            return Err(payload_too_large("paste", lim, !done));
         }
-        Err(e) => Err(e.into()),
+        paste.extend_from_slice(&chunk);
     }
+    String::from_utf8(paste).map_err(|e| {
+        ApiError::new_with_status(
+            StatusCode::BAD_REQUEST,
+            format!("Request body didn't contain valid UTF-8: {e}"),
+        )
+    })
 }
 
 async fn upload_paste(
-    State(webdata): State<Arc<WebData>>,
-    paste: Result<String, StringRejection>,
+    State(store): State<StoreData>,
+    ip: Option<Extension<ClientIp>>,
+    headers: HeaderMap,
+    body: Body,
 ) -> Result<ApiError, ApiError> {
-    let WebData {
-        link_prefix,
-        paste: storage,
-        ..
-    } = webdata.as_ref();
-    let paste = handle_paste(paste, storage.get_max_siz())?;
-    let fname = storage.gen_new_fname("txt");
-    let mut upload = storage.get_base();
-    upload.push(&fname);
-    // if the file fails beyond this point, it will be stale in the FIFO. oh well.
-    if let Some(del) = storage.push(&upload) {
-        background_rm_file(del);
+    let storage = store.storage();
+    let paste = read_paste(body, storage.get_max_siz()).await?;
+    let mut meta = UploadMeta::new(
+        paste.len() as u64,
+        "txt",
+        &Sha256::digest(&paste),
+        ip.map(|Extension(ClientIp(ip))| storage.hash_ip(ip)),
+    );
+    let vanity = vanity_name(&store, storage, &headers, "txt")?;
+    if vanity.is_none()
+        && let Some(fname) = storage.find_dup(&meta.sha256).await
+    {
+        return Ok(ApiError::new_ok(store.link(&fname)));
     }
-
-    let fguard = DropFsGuard::new(&upload);
-    tokio::fs::write(&upload, paste).await?;
+    let stored = if storage.compress() {
+        // what the store holds is what counts against its limits.
+        let gz = compress(&paste)?;
+        meta.size = gz.len() as u64;
+        gz
+    } else {
+        paste.into_bytes()
+    };
+    storage.reserve_space(meta.size).await?;
+    let (staged, mut file) = storage.create_staging().await?;
+    let fguard = DropFsGuard::new(&staged);
+    file.write_all(&stored).await?;
+    file.flush().await?;
+    let fname = match vanity {
+        Some(fname) => match storage.publish_as(&staged, &fname).await {
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                return Err(name_taken(&fname));
+            }
+            res => res.map(|_| fname)?,
+        },
+        None => storage.publish(&staged, "txt").await?,
+    };
     fguard.defuse();
-    Ok(ApiError::new_ok(format!("{link_prefix}/p/{fname}")))
+    storage.record(&fname, &meta).await;
+    Ok(ApiError::new_ok(store.link(&fname))
+        .with_delete_token(storage.deletion_token(&fname, &meta)))
+}
+
+/// Valid UTF-8 can never start with this, so compressed pastes are told apart by their contents.
+#[cfg(feature = "serve-files")]
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+fn compress(paste: &str) -> std::io::Result<Vec<u8>> {
+    let mut enc = GzEncoder::new(Vec::with_capacity(paste.len() / 4), Compression::best());
+    enc.write_all(paste.as_bytes())?;
+    enc.finish()
+}
+
+#[cfg(feature = "serve-files")]
+fn accepts_gzip(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|coding| {
+            let mut params = coding.split(';').map(str::trim);
+            let name = params.next().unwrap_or_default();
+            (name.eq_ignore_ascii_case("gzip") || name == "*")
+                && !params.any(|p| {
+                    p.strip_prefix("q=")
+                        .and_then(|q| q.parse::<f32>().ok())
+                        .is_some_and(|q| q == 0.0)
+                })
+        })
+}
+
+/// Compressed pastes are sent as is to clients that accept gzip, and inflated for the rest.
+/// Every paste is served through here, since compress may have been on when it was stored.
+#[cfg(feature = "serve-files")]
+async fn get_paste(
+    State(store): State<StoreData>,
+    UrlPath(fname): UrlPath<String>,
+    headers: HeaderMap,
+) -> Result<axum::response::Response, ApiError> {
+    let (mut parts, body) = get_upload(store.storage(), &fname).await?.into_parts();
+    let mut body = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .map_err(ApiError::new)?;
+    if body.starts_with(&GZIP_MAGIC) {
+        parts
+            .headers
+            .insert(VARY, HeaderValue::from_static("accept-encoding"));
+        if accepts_gzip(&headers) {
+            parts
+                .headers
+                .insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+        } else {
+            let mut plain = Vec::with_capacity(store.storage().get_max_siz());
+            GzDecoder::new(body.as_ref()).read_to_end(&mut plain)?;
+            parts.headers.insert(CONTENT_LENGTH, plain.len().into());
+            body = plain.into();
+        }
+    }
+    Ok(axum::response::Response::from_parts(parts, body.into()))
+}
+
+async fn delete_paste(
+    State(store): State<StoreData>,
+    UrlPath(fname): UrlPath<String>,
+    headers: HeaderMap,
+) -> Result<ApiError, ApiError> {
+    delete_upload(store.storage(), &fname, &headers).await
 }
 
 #[cfg(not(feature = "serve-files"))]
@@ -90,24 +211,32 @@ async fn get_file_err() -> axum::response::Response {
         .unwrap()
 }
 
-pub fn upload_route(lim: usize) -> Router<Arc<WebData>> {
-    Router::new().route("/paste", post(upload_paste)).layer(
-        ServiceBuilder::new()
-            .layer(DefaultBodyLimit::max(lim))
-            .layer(HeaderSizeLim::from(lim)),
-    )
+pub fn upload_route(store: StoreData) -> Router<Arc<WebData>> {
+    let storage = store.storage();
+    Router::new()
+        .route(storage.upload_path(), post(upload_paste))
+        .layer(
+            ServiceBuilder::new()
+                .layer(DefaultBodyLimit::disable())
+                .layer(HeaderSizeLim::from(storage.max_siz_handle())),
+        )
+        .with_state(store)
 }
 
-pub fn serve_route<P: AsRef<Path>>(_p: P) -> Router<Arc<WebData>> {
-    let r = Router::new();
+pub fn serve_route(store: StoreData) -> Router<Arc<WebData>> {
+    // DELETE shares the path with serving pastes.
+    let del = delete(delete_paste.layer(HeaderCsrf));
+    let storage = store.storage();
+    // not a plain file server even with compress off, the store may hold pastes from when it was on.
     #[cfg(feature = "serve-files")]
-    let r = r.nest_service(
-        "/p",
-        ServiceBuilder::new()
-            .layer(Utf8TextPlain)
-            .service(tower_http::services::ServeDir::new(_p).with_buf_chunk_size(256 * 1024)),
-    );
+    let (del, r) = (del.get(get_paste), Router::new());
     #[cfg(not(feature = "serve-files"))]
-    let r = r.route("/p/{*any}", axum::routing::get(get_file_err));
-    r
+    let (del, r) = (
+        del.fallback(get_file_err),
+        Router::new().fallback(get_file_err),
+    );
+    let r = Router::new().nest(storage.route(), r.route("/{fname}", del));
+    #[cfg(feature = "serve-files")]
+    let r = r.layer(ServiceBuilder::new().option_layer(Touch::lru(&store)));
+    r.with_state(store.clone())
 }
@@ -0,0 +1,699 @@
+// Copyright (c) 2026, Anthony DeDominic <adedomin@gmail.com>
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+// Resumable uploads with the tus protocol, 1.0 core and creation: https://tus.io/protocols/resumable-upload
+// A client POSTs the length to <upload>/tus, then PATCHes the bytes to the Location it gets back,
+// asking with HEAD how much arrived whenever the connection drops.
+use std::{io, path::PathBuf, sync::Arc};
+
+use axum::{
+    Extension, Router,
+    body::Body,
+    extract::{DefaultBodyLimit, Path as UrlPath, State},
+    handler::Handler,
+    http::{
+        HeaderMap, HeaderValue, StatusCode,
+        header::{CACHE_CONTROL, CONTENT_LENGTH, CONTENT_TYPE, LOCATION},
+    },
+    middleware::map_response,
+    response::Response,
+    routing::{head, options, post},
+};
+use futures_util::StreamExt;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tower::ServiceBuilder;
+
+use super::image::{DELETE_TOKEN, payload_too_large};
+use crate::{
+    config::StorageState,
+    middleware::{csrf::HeaderCsrf, ratelim::ClientIp},
+    models::{
+        api::ApiError,
+        meta::{UploadMeta, unix_now},
+        mime::{SNIFF_LEN, detect_ext},
+        strip::strip_file,
+        validate::{ValidateErr, validate_file},
+        webdata::{StoreData, WebData},
+    },
+};
+
+const TUS_VERSION: &str = "1.0.0";
+const TUS_RESUMABLE: &str = "Tus-Resumable";
+const UPLOAD_LENGTH: &str = "Upload-Length";
+const UPLOAD_OFFSET: &str = "Upload-Offset";
+const OFFSET_STREAM: &str = "application/offset+octet-stream";
+/// Not part of tus; where the finished upload can be found.
+const UPLOAD_LINK: &str = "X-Upload-Link";
+
+/// What we know about a resumable upload besides its bytes, kept next to them as <id>.json.
+#[derive(Serialize, Deserialize)]
+struct TusInfo {
+    length: u64,
+    created: u64,
+    /// Detected as soon as enough bytes arrive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ext: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ip_hash: Option<String>,
+    /// Set once published, so a client that missed the last reply can still HEAD for the link.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    done: Option<Done>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Done {
+    name: String,
+    /// None for a duplicate of someone else's upload.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+}
+
+struct TusUpload {
+    data: PathBuf,
+    info: PathBuf,
+}
+
+impl TusUpload {
+    fn new(storage: &StorageState, id: &str) -> Self {
+        let dir = storage.tus_dir();
+        Self {
+            data: dir.join(id),
+            info: dir.join(format!("{id}.json")),
+        }
+    }
+
+    /// An upload from a URL, which only ever has ids we made.
+    fn find(storage: &StorageState, id: &str) -> Result<Self, ApiError> {
+        if id.len() == 32 && id.bytes().all(|c| c.is_ascii_hexdigit()) {
+            Ok(Self::new(storage, id))
+        } else {
+            Err(no_such_upload())
+        }
+    }
+
+    async fn read_info(&self) -> Result<TusInfo, ApiError> {
+        match tokio::fs::read(&self.info).await {
+            Ok(info) => Ok(serde_json::from_slice(&info).map_err(io::Error::from)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Err(no_such_upload()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn write_info(&self, info: &TusInfo) -> io::Result<()> {
+        let mut tmp = self.info.clone().into_os_string();
+        tmp.push(".tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec(info)?).await?;
+        tokio::fs::rename(&tmp, &self.info).await
+    }
+
+    /// How many bytes arrived so far.
+    async fn offset(&self) -> Result<u64, ApiError> {
+        match tokio::fs::metadata(&self.data).await {
+            Ok(meta) => Ok(meta.len()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Err(no_such_upload()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn remove(&self) {
+        _ = tokio::fs::remove_file(&self.data).await;
+        _ = tokio::fs::remove_file(&self.info).await;
+    }
+}
+
+/// An upload with a PATCH in flight, until dropped.
+struct Busy<'a> {
+    storage: &'a StorageState,
+    id: &'a str,
+}
+
+impl<'a> Busy<'a> {
+    fn take(storage: &'a StorageState, id: &'a str) -> Option<Self> {
+        storage.claim_tus(id).then_some(Self { storage, id })
+    }
+}
+
+impl Drop for Busy<'_> {
+    fn drop(&mut self) {
+        self.storage.release_tus(self.id);
+    }
+}
+
+fn no_such_upload() -> ApiError {
+    ApiError::new_with_status(StatusCode::NOT_FOUND, "No such upload.")
+}
+
+fn check_version(headers: &HeaderMap) -> Result<(), ApiError> {
+    if headers.get(TUS_RESUMABLE).is_some_and(|v| v == TUS_VERSION) {
+        Ok(())
+    } else {
+        Err(ApiError::new_with_status(
+            StatusCode::PRECONDITION_FAILED,
+            format!("Only {TUS_RESUMABLE}: {TUS_VERSION} is supported."),
+        ))
+    }
+}
+
+fn header_u64(headers: &HeaderMap, name: &str) -> Result<u64, ApiError> {
+    headers
+        .get(name)
+        .ok_or(ApiError::new_with_status(
+            StatusCode::BAD_REQUEST,
+            format!("Missing {name} header."),
+        ))?
+        .to_str()
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .ok_or(ApiError::new_with_status(
+            StatusCode::BAD_REQUEST,
+            format!("{name} must be a number."),
+        ))
+}
+
+/// Every tus response says which version it speaks, errors included.
+async fn tus_resumable(mut res: Response) -> Response {
+    res.headers_mut()
+        .insert(TUS_RESUMABLE, HeaderValue::from_static(TUS_VERSION));
+    if res.status() == StatusCode::PRECONDITION_FAILED {
+        res.headers_mut()
+            .insert("Tus-Version", HeaderValue::from_static(TUS_VERSION));
+    }
+    res
+}
+
+async fn tus_options(State(store): State<StoreData>) -> Response {
+    Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .header("Tus-Version", TUS_VERSION)
+        .header("Tus-Extension", "creation")
+        .header("Tus-Max-Size", store.storage().get_max_siz())
+        .body(Body::empty())
+        .unwrap()
+}
+
+async fn tus_create(
+    State(store): State<StoreData>,
+    ip: Option<Extension<ClientIp>>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    check_version(&headers)?;
+    let storage = store.storage();
+    let length = header_u64(&headers, UPLOAD_LENGTH)?;
+    let max_siz = storage.get_max_siz();
+    if length > max_siz as u64 {
+        return Err(payload_too_large("upload", max_siz, false));
+    }
+    storage.reserve_space(length).await?;
+    let (id, upload) = loop {
+        let id = format!("{:032x}", rand::rng().random::<u128>());
+        let upload = TusUpload::new(storage, &id);
+        match tokio::fs::File::options()
+            .write(true)
+            .create_new(true)
+            .open(&upload.data)
+            .await
+        {
+            Ok(_) => break (id, upload),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    };
+    let info = TusInfo {
+        length,
+        created: unix_now(),
+        ext: None,
+        ip_hash: ip.map(|Extension(ClientIp(ip))| storage.hash_ip(ip)),
+        done: None,
+    };
+    if let Err(e) = upload.write_info(&info).await {
+        upload.remove().await;
+        return Err(e.into());
+    }
+    let tus_path = storage.tus_path().unwrap_or_default();
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .header(LOCATION, store.url(&format!("{tus_path}/{id}")))
+        .body(Body::empty())
+        .unwrap())
+}
+
+/// Tell the client where its finished upload went.
+fn finished(store: &StoreData, length: u64, done: &Done, code: StatusCode) -> Response {
+    let res = Response::builder()
+        .status(code)
+        .header(UPLOAD_OFFSET, length)
+        .header(UPLOAD_LINK, store.link(&done.name));
+    let res = match done.token {
+        Some(ref token) => res.header(DELETE_TOKEN, token),
+        None => res,
+    };
+    res.body(Body::empty()).unwrap()
+}
+
+async fn tus_head(
+    State(store): State<StoreData>,
+    UrlPath(id): UrlPath<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    check_version(&headers)?;
+    let upload = TusUpload::find(store.storage(), &id)?;
+    let info = upload.read_info().await?;
+    let mut res = match info.done {
+        Some(ref done) => finished(&store, info.length, done, StatusCode::OK),
+        None => Response::builder()
+            .status(StatusCode::OK)
+            .header(UPLOAD_OFFSET, upload.offset().await?)
+            .body(Body::empty())
+            .unwrap(),
+    };
+    let headers = res.headers_mut();
+    headers.insert(UPLOAD_LENGTH, info.length.into());
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    Ok(res)
+}
+
+async fn tus_patch(
+    State(store): State<StoreData>,
+    UrlPath(id): UrlPath<String>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Response, ApiError> {
+    // most of these are answered without reading the body.
+    patch_upload(&store, &id, &headers, body)
+        .await
+        .map_err(ApiError::close_conn)
+}
+
+async fn patch_upload(
+    store: &StoreData,
+    id: &str,
+    headers: &HeaderMap,
+    body: Body,
+) -> Result<Response, ApiError> {
+    check_version(headers)?;
+    if headers.get(CONTENT_TYPE).is_none_or(|v| v != OFFSET_STREAM) {
+        return Err(ApiError::new_with_status(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("Content-Type must be {OFFSET_STREAM}."),
+        ));
+    }
+    let storage = store.storage();
+    let upload = TusUpload::find(storage, id)?;
+    let _busy = Busy::take(storage, id).ok_or(ApiError::new_with_status(
+        StatusCode::LOCKED,
+        "Another request is still writing to this upload.",
+    ))?;
+    let mut info = upload.read_info().await?;
+    let offset = header_u64(headers, UPLOAD_OFFSET)?;
+    let current = match info.done {
+        Some(_) => info.length,
+        None => upload.offset().await?,
+    };
+    if offset != current {
+        return Err(ApiError::new_with_status(
+            StatusCode::CONFLICT,
+            format!("{UPLOAD_OFFSET} is {current}."),
+        ));
+    }
+    if let Some(ref done) = info.done {
+        // the client missed our reply to the last PATCH.
+        return Ok(finished(store, info.length, done, StatusCode::NO_CONTENT));
+    }
+    // siz may have been lowered by a reload since the upload was created.
+    let max_siz = storage.get_max_siz();
+    if info.length > max_siz as u64 {
+        upload.remove().await;
+        return Err(payload_too_large("upload", max_siz, true));
+    }
+    let remaining = info.length - offset;
+    let too_much = || {
+        ApiError::new_with_status(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("This upload only has {remaining} bytes left."),
+        )
+    };
+    if headers
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok()?.parse::<u64>().ok())
+        .is_some_and(|len| len > remaining)
+    {
+        return Err(too_much());
+    }
+
+    // whatever arrives before the connection drops is kept, that's the point.
+    let mut file = tokio::fs::File::options()
+        .append(true)
+        .open(&upload.data)
+        .await?;
+    let mut body = body.into_data_stream();
+    let mut written = 0;
+    let mut res = Ok(());
+    while let Some(chunk) = body.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                res = Err(e.into());
+                break;
+            }
+        };
+        if written + chunk.len() as u64 > remaining {
+            res = Err(too_much());
+            break;
+        }
+        if let Err(e) = file.write_all(&chunk).await {
+            res = Err(e.into());
+            break;
+        }
+        written += chunk.len() as u64;
+    }
+    file.flush().await?;
+    drop(file);
+    let offset = upload.offset().await?;
+
+    if info.ext.is_none() && (offset >= SNIFF_LEN as u64 || offset == info.length) {
+        let mut start = vec![];
+        tokio::fs::File::open(&upload.data)
+            .await?
+            .take(SNIFF_LEN as u64)
+            .read_to_end(&mut start)
+            .await?;
+        let Some(ext) = detect_ext(&start) else {
+            upload.remove().await;
+            return Err(ApiError::new_with_status(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "Unsupported image, video or audio format.",
+            ));
+        };
+        if !storage.accepts(ext) {
+            upload.remove().await;
+            return Err(ApiError::new_with_status(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                format!("The {} store doesn't take {ext} files.", storage.name()),
+            ));
+        }
+        info.ext = Some(ext.to_owned());
+        upload.write_info(&info).await?;
+    }
+    res?;
+    if offset < info.length {
+        return Ok(Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .header(UPLOAD_OFFSET, offset)
+            .body(Body::empty())
+            .unwrap());
+    }
+
+    let done = publish(storage, &upload, &info).await?;
+    let res = finished(store, info.length, &done, StatusCode::NO_CONTENT);
+    info.done = Some(done);
+    upload.write_info(&info).await?;
+    Ok(res)
+}
+
+/// Move a complete upload into the store, like any other upload.
+async fn publish(
+    storage: &StorageState,
+    upload: &TusUpload,
+    info: &TusInfo,
+) -> Result<Done, ApiError> {
+    // the extension is always detected by the time every byte is in.
+    let ext = info.ext.as_deref().unwrap_or_default();
+    let checked = async {
+        if storage.validates() {
+            validate_file(ext, &upload.data).await?;
+        }
+        if storage.strips_metadata() {
+            return strip_file(ext, &upload.data).await;
+        }
+        Ok(None)
+    };
+    let stripped = match checked.await {
+        Ok(stripped) => stripped,
+        // a bad file won't get any better, unlike a failing disk.
+        Err(ValidateErr::Io(e)) => return Err(e.into()),
+        Err(e) => {
+            upload.remove().await;
+            return Err(e.into());
+        }
+    };
+    let (size, sha256) = match stripped {
+        Some(stripped) => stripped,
+        None => {
+            let data = upload.data.clone();
+            let sha256 = tokio::task::spawn_blocking(move || {
+                let mut hasher = Sha256::new();
+                io::copy(&mut std::fs::File::open(data)?, &mut hasher)?;
+                io::Result::Ok(hasher.finalize().into())
+            })
+            .await
+            .map_err(io::Error::other)??;
+            (info.length, sha256)
+        }
+    };
+    let meta = UploadMeta::new(size, ext, &sha256, info.ip_hash.clone());
+    if let Some(name) = storage.find_dup(&meta.sha256).await {
+        _ = tokio::fs::remove_file(&upload.data).await;
+        return Ok(Done { name, token: None });
+    }
+    let name = storage.publish(&upload.data, ext).await?;
+    storage.record(&name, &meta).await;
+    let token = Some(storage.deletion_token(&name, &meta));
+    Ok(Done { name, token })
+}
+
+/// Creating uploads, which is ratelimited like any other upload.
+pub fn upload_route(store: StoreData) -> Router<Arc<WebData>> {
+    let Some(path) = store.storage().tus_path() else {
+        return Router::new();
+    };
+    Router::new()
+        .route(&path, post(tus_create))
+        .layer(map_response(tus_resumable))
+        .with_state(store)
+}
+
+/// Resuming uploads, which were already counted when they were created.
+pub fn resume_route(store: StoreData) -> Router<Arc<WebData>> {
+    let Some(path) = store.storage().tus_path() else {
+        return Router::new();
+    };
+    Router::new()
+        .route(&path, options(tus_options))
+        .route(
+            &format!("{path}/{{id}}"),
+            head(tus_head)
+                .patch(tus_patch.layer(HeaderCsrf))
+                .options(tus_options),
+        )
+        .layer(
+            ServiceBuilder::new()
+                .layer(DefaultBodyLimit::disable())
+                .layer(map_response(tus_resumable)),
+        )
+        .with_state(store)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+
+    use axum::http::{Method, Request};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{config::load_config, models::fixtures::png};
+
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(fut)
+    }
+
+    /// An image store taking resumable uploads in a fresh directory, removed when dropped.
+    struct TestStore {
+        dir: PathBuf,
+        store: StoreData,
+        app: Router,
+    }
+
+    impl TestStore {
+        async fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "imageshare-tus-{:016x}",
+                rand::rng().random::<u64>()
+            ));
+            std::fs::create_dir_all(&dir).unwrap();
+            let config = dir.join("config.json");
+            let settings = serde_json::json!({
+                "image": { "dir": dir.join("i"), "tus": true },
+                "paste": { "dir": dir.join("p") },
+            });
+            std::fs::write(&config, settings.to_string()).unwrap();
+            let webdata = load_config(Some(config))
+                .unwrap()
+                .get_webdata()
+                .await
+                .unwrap();
+            let store = StoreData::new(webdata.clone(), 0);
+            let app = upload_route(store.clone())
+                .merge(resume_route(store.clone()))
+                .with_state(webdata);
+            Self { dir, store, app }
+        }
+
+        async fn send(&self, req: Request<Body>) -> Response {
+            self.app.clone().oneshot(req).await.unwrap()
+        }
+
+        async fn create(&self, length: usize) -> String {
+            let res = self
+                .send(
+                    Request::post("/upload/tus")
+                        .header(TUS_RESUMABLE, TUS_VERSION)
+                        .header(UPLOAD_LENGTH, length)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await;
+            assert_eq!(res.status(), StatusCode::CREATED);
+            res.headers()[LOCATION].to_str().unwrap().to_owned()
+        }
+
+        async fn patch(&self, location: &str, offset: usize, bytes: &[u8]) -> Response {
+            self.send(
+                Request::patch(location)
+                    .header(TUS_RESUMABLE, TUS_VERSION)
+                    .header(CONTENT_TYPE, OFFSET_STREAM)
+                    .header(UPLOAD_OFFSET, offset)
+                    .body(Body::from(bytes.to_vec()))
+                    .unwrap(),
+            )
+            .await
+        }
+    }
+
+    impl Drop for TestStore {
+        fn drop(&mut self) {
+            _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn header<'a>(res: &'a Response, name: &str) -> &'a str {
+        res.headers()[name].to_str().unwrap()
+    }
+
+    #[test]
+    fn upload_in_pieces() {
+        block_on(async {
+            let t = TestStore::new().await;
+            let png = png(&[]);
+            let location = t.create(png.len()).await;
+            assert!(location.starts_with("/upload/tus/"));
+
+            let res = t.patch(&location, 0, &png[..20]).await;
+            assert_eq!(res.status(), StatusCode::NO_CONTENT);
+            assert_eq!(header(&res, UPLOAD_OFFSET), "20");
+
+            let head = Request::head(&location)
+                .header(TUS_RESUMABLE, TUS_VERSION)
+                .body(Body::empty())
+                .unwrap();
+            let res = t.send(head).await;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(header(&res, UPLOAD_OFFSET), "20");
+            assert_eq!(header(&res, UPLOAD_LENGTH), png.len().to_string());
+            assert_eq!(header(&res, TUS_RESUMABLE), TUS_VERSION);
+
+            let res = t.patch(&location, 20, &png[20..]).await;
+            assert_eq!(res.status(), StatusCode::NO_CONTENT);
+            assert_eq!(header(&res, UPLOAD_OFFSET), png.len().to_string());
+            let link = header(&res, UPLOAD_LINK);
+            let name = link.strip_prefix("/i/").unwrap();
+            assert!(name.ends_with(".png"));
+            assert!(!header(&res, DELETE_TOKEN).is_empty());
+
+            // published into the store, and the staged bytes are gone.
+            let storage = t.store.storage();
+            let entry = storage.backend().stat(name).await.unwrap().unwrap();
+            assert_eq!(entry.size, png.len() as u64);
+            let id = location.rsplit('/').next().unwrap();
+            assert!(!storage.tus_dir().join(id).exists());
+            assert_eq!(storage.read_meta(name).await.unwrap().ext, "png");
+
+            // a client that missed the reply can still find its link.
+            let head = Request::head(&location)
+                .header(TUS_RESUMABLE, TUS_VERSION)
+                .body(Body::empty())
+                .unwrap();
+            let res = t.send(head).await;
+            assert_eq!(header(&res, UPLOAD_LINK), link);
+        });
+    }
+
+    #[test]
+    fn offset_mismatch() {
+        block_on(async {
+            let t = TestStore::new().await;
+            let png = png(&[]);
+            let location = t.create(png.len()).await;
+            let res = t.patch(&location, 10, &png[10..]).await;
+            assert_eq!(res.status(), StatusCode::CONFLICT);
+
+            let res = t.patch(&location, 0, &png[..10]).await;
+            assert_eq!(res.status(), StatusCode::NO_CONTENT);
+            let res = t.patch(&location, 0, &png[..10]).await;
+            assert_eq!(res.status(), StatusCode::CONFLICT);
+        });
+    }
+
+    #[test]
+    fn wrong_version() {
+        block_on(async {
+            let t = TestStore::new().await;
+            let create = Request::post("/upload/tus")
+                .header(TUS_RESUMABLE, "0.2.2")
+                .header(UPLOAD_LENGTH, 10)
+                .body(Body::empty())
+                .unwrap();
+            let res = t.send(create).await;
+            assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+            assert_eq!(header(&res, "Tus-Version"), TUS_VERSION);
+
+            let location = t.create(10).await;
+            let patch = Request::builder()
+                .method(Method::PATCH)
+                .uri(&location)
+                .header(CONTENT_TYPE, OFFSET_STREAM)
+                .header(UPLOAD_OFFSET, 0)
+                .body(Body::from(vec![0; 10]))
+                .unwrap();
+            let res = t.send(patch).await;
+            assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+        });
+    }
+
+    #[test]
+    fn too_long() {
+        block_on(async {
+            let t = TestStore::new().await;
+            let location = t.create(10).await;
+            let res = t.patch(&location, 0, &[0; 11]).await;
+            assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        });
+    }
+}